@@ -1,13 +1,17 @@
 use std::{
     fs::File,
-    io::{BufWriter, stdout},
-    path::PathBuf,
+    io::{BufWriter, Write, stdout},
+    path::{Path, PathBuf},
 };
 
 use clap::{Parser, Subcommand};
-use miette::IntoDiagnostic;
+use convert_case::{Case, Casing};
+use miette::{IntoDiagnostic, Severity};
 
-use crate::{context::Context};
+use crate::{
+    context::Context,
+    json::{ExampleMode, Json, JsonDocument, infer_kdl},
+};
 
 #[derive(Debug, Parser)]
 #[command(version, about)]
@@ -34,6 +38,68 @@ pub enum Cmd {
         #[clap(long, short)]
         output: Option<PathBuf>,
     },
+
+    /// Generate a `package:test` suite that checks JSON and builder round-trips, equality and
+    /// `hashCode` for every generated type
+    GenerateTests {
+        /// Path to the KDL config file
+        #[clap(long, short)]
+        input: PathBuf,
+
+        /// How the tests import the generated Dart, e.g. `package:my_app/models.dart`
+        #[clap(long)]
+        import: String,
+
+        /// The path to write the tests to. If not provided, they will be printed to stdout
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Generate example JSON for every class, union and enum
+    Examples {
+        /// Path to the KDL config file
+        #[clap(long, short)]
+        input: PathBuf,
+
+        /// Whether to include only the keys that are required, or every key
+        #[clap(long, value_enum, default_value_t = ExampleMode::Maximal)]
+        mode: ExampleMode,
+
+        /// A directory to write a `<Type>.json` file for each type to. If not provided, a JSON
+        /// object with an example for each type will be printed to stdout
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check that a JSON document can be decoded by the generated `fromJson` of a type
+    CheckJson {
+        /// Path to the KDL config file
+        #[clap(long, short, visible_alias = "schema")]
+        input: PathBuf,
+
+        /// The class, union or enum that the document should decode as
+        #[clap(long = "type", short)]
+        ty: String,
+
+        /// Path to the JSON document
+        path: PathBuf,
+    },
+
+    /// Infer a config from sample JSON documents, with a class for the top-level objects and
+    /// for every nested object
+    Infer {
+        /// Paths to JSON files, each containing an object or an array of objects
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+
+        /// The name of the top-level class. Defaults to the name of the first file, in PascalCase
+        #[clap(long)]
+        name: Option<String>,
+
+        /// The path to write the config to. If not provided, it will be printed to stdout
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
 }
 
 pub fn run(args: &Args) -> miette::Result<()> {
@@ -46,20 +112,152 @@ pub fn run(args: &Args) -> miette::Result<()> {
             let context = Context::from_path(input)?;
             context.validate(args.deny_warnings)?;
 
-            match &output {
-                Some(output) => {
-                    let output = File::create(output).into_diagnostic()?;
-                    let mut output = BufWriter::new(output);
+            write_output(output.as_deref(), |mut out| context.codegen(&mut out))?;
+        }
+        Cmd::GenerateTests {
+            input,
+            import,
+            output,
+        } => {
+            let context = Context::from_path(input)?;
+            context.validate(args.deny_warnings)?;
+
+            write_output(output.as_deref(), |mut out| {
+                context.codegen_tests(&mut out, import)
+            })?;
+        }
+        Cmd::Examples {
+            input,
+            mode,
+            output,
+        } => {
+            let context = Context::from_path(input)?;
+            context.validate(args.deny_warnings)?;
+
+            let mut examples = vec![];
+            for (name, example) in context.json_examples(*mode) {
+                match example {
+                    Some(example) => examples.push((name, example)),
+                    None => {
+                        let warning = miette::miette!(
+                            severity = Severity::Warning,
+                            help = "external types without a `from-json` and generic types \
+                                    without a conversion have no known JSON, and types which \
+                                    always contain themselves can't be constructed",
+                            "No example can be made for `{name}`"
+                        );
+                        if args.deny_warnings {
+                            return Err(warning);
+                        }
+                        eprintln!("{warning:?}");
+                    }
+                }
+            }
 
-                    context.codegen(&mut output)?;
+            match output {
+                Some(dir) => {
+                    std::fs::create_dir_all(dir).into_diagnostic()?;
+                    for (name, example) in examples {
+                        let path = dir.join(format!("{name}.json"));
+                        std::fs::write(path, format!("{example}\n")).into_diagnostic()?;
+                    }
                 }
                 None => {
-                    let mut output = BufWriter::new(stdout().lock());
-                    context.codegen(&mut output)?;
+                    let examples = examples
+                        .into_iter()
+                        .map(|(name, example)| (name.to_string(), example))
+                        .collect();
+                    writeln!(stdout().lock(), "{}", Json::Object(examples)).into_diagnostic()?;
                 }
             }
         }
+        Cmd::CheckJson { input, ty, path } => {
+            let context = Context::from_path(input)?;
+            context.validate(args.deny_warnings)?;
+
+            let document = JsonDocument::from_path(path)?;
+            context.check_json(&document, ty)?;
+        }
+        Cmd::Infer {
+            files,
+            name,
+            output,
+        } => {
+            let documents = files
+                .iter()
+                .map(|path| JsonDocument::from_path(path))
+                .collect::<miette::Result<Vec<_>>>()?;
+
+            let name = match name {
+                Some(name) => name.clone(),
+                None => files[0]
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_case(Case::Pascal))
+                    .unwrap_or_default(),
+            };
+
+            let kdl = infer_kdl(&documents, &name)?;
+            write_output(output.as_deref(), |out| {
+                out.write_all(kdl.as_bytes()).into_diagnostic()
+            })?;
+        }
     }
 
     Ok(())
 }
+
+/// Calls `write` with the file at `output`, or with stdout if there is no `output`
+fn write_output(
+    output: Option<&Path>,
+    write: impl FnOnce(&mut dyn Write) -> miette::Result<()>,
+) -> miette::Result<()> {
+    match output {
+        Some(output) => {
+            let output = File::create(output).into_diagnostic()?;
+            let mut output = BufWriter::new(output);
+
+            write(&mut output)
+        }
+        None => {
+            let mut output = BufWriter::new(stdout().lock());
+            write(&mut output)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn check_json_accepts_schema() {
+        let tempdir = TempDir::new("dart-typegen-test").unwrap();
+        let schema = tempdir.path().join("api.kdl");
+        let valid = tempdir.path().join("valid.json");
+        let invalid = tempdir.path().join("invalid.json");
+        std::fs::write(&schema, r#"class "TopLevel" { field "id" type="int"; }"#).unwrap();
+        std::fs::write(&valid, r#"{"id": 1}"#).unwrap();
+        std::fs::write(&invalid, r#"{"id": "one"}"#).unwrap();
+
+        let check = |flag: &str, payload: &Path| {
+            let args = Args::try_parse_from([
+                "dart-typegen".as_ref(),
+                "check-json".as_ref(),
+                flag.as_ref(),
+                schema.as_os_str(),
+                "--type".as_ref(),
+                "TopLevel".as_ref(),
+                payload.as_os_str(),
+            ])
+            .unwrap();
+            run(&args)
+        };
+
+        for flag in ["--schema", "--input", "-i"] {
+            assert!(check(flag, &valid).is_ok(), "{flag}");
+            assert!(check(flag, &invalid).is_err(), "{flag}");
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use super::*;
+
+/// A single check derived from a field constraint, such as `min 0`
+struct Constraint {
+    /// A Dart expression that is true when the constraint holds
+    valid: String,
+    /// A Dart expression that is true when the constraint is violated
+    invalid: String,
+    /// A human-readable description of the violation
+    message: String,
+    /// Whether the check can be used in the `assert`s of a `const` constructor
+    const_safe: bool,
+}
+
+impl Context {
+    pub(super) fn has_constraints(&self, class: &Class) -> bool {
+        class
+            .fields
+            .iter()
+            .any(|field| !self.field_constraints(field, &field.name).is_empty())
+    }
+
+    pub(crate) fn validates_from_json(&self, class: &Class) -> bool {
+        let validate = class
+            .validate_from_json
+            .as_ref()
+            .or_else(|| {
+                self.library
+                    .defaults
+                    .as_ref()
+                    .and_then(|d| d.class.as_ref()?.validate_from_json.as_ref())
+            })
+            .map(|spanned| spanned.value)
+            .unwrap_or(false);
+
+        validate && self.has_constraints(class)
+    }
+
+    /// The `assert`s to put in the initializer list of the constructor. Only the checks that are
+    /// allowed in a `const` constructor are included, the rest are left to `validate()`
+    pub(super) fn constraint_asserts(&self, class: &Class) -> Vec<String> {
+        let mut asserts = vec![];
+
+        for field in &class.fields {
+            let name = &field.name;
+            let nullable = self.field_is_nullable(field);
+
+            for constraint in self.field_constraints(field, name) {
+                if !constraint.const_safe {
+                    continue;
+                }
+
+                let valid = match nullable {
+                    true => format!("{name} == null || {}", constraint.valid),
+                    false => constraint.valid,
+                };
+                let message = dart_string_literal(&constraint.message);
+
+                asserts.push(format!("assert({valid}, {message})"));
+            }
+        }
+
+        asserts
+    }
+
+    /// Generates `List<String> validate()`, which returns a message for every violated
+    /// constraint. In the builder of a partial builder class, unset fields are skipped
+    pub(super) fn generate_validate(
+        &self,
+        buf: &mut String,
+        class: &Class,
+        builder: bool,
+    ) -> std::fmt::Result {
+        writeln!(
+            buf,
+            "/// Checks the constraints on the fields of this class, returning a"
+        )?;
+        writeln!(buf, "/// description of each one that is violated")?;
+        write!(buf, "List<String> validate() => ")?;
+        self.write_violations(buf, class, builder, |name| name.to_string())?;
+        writeln!(buf, ";")?;
+        writeln!(buf)?;
+
+        Ok(())
+    }
+
+    /// Writes a list literal containing a message for every violated constraint. `value` maps a
+    /// field name to the expression holding its value, and `builder` is set when the values are
+    /// the fields of a builder
+    pub(super) fn write_violations(
+        &self,
+        buf: &mut String,
+        class: &Class,
+        builder: bool,
+        value: impl Fn(&str) -> String,
+    ) -> std::fmt::Result {
+        writeln!(buf, "<String>[")?;
+
+        for field in &class.fields {
+            let value = value(&field.name);
+            let nullable = self.field_is_nullable(field)
+                || (builder && self.is_partial_builder_field(class, field));
+
+            for constraint in self.field_constraints(field, &value) {
+                let message = dart_string_literal(&constraint.message);
+                let invalid = constraint.invalid;
+
+                match nullable {
+                    true => writeln!(
+                        buf,
+                        "if ({value} case final {value}? when {invalid}) {message},"
+                    )?,
+                    false => writeln!(buf, "if ({invalid}) {message},")?,
+                }
+            }
+        }
+
+        write!(buf, "]")?;
+
+        Ok(())
+    }
+
+    /// The checks for every constraint on `field`, where `value` is the (non-null) expression
+    /// being checked
+    fn field_constraints(&self, field: &Field, value: &str) -> Vec<Constraint> {
+        let name = &field.name;
+        let mut constraints = vec![];
+
+        if let Some(min) = &field.min {
+            let min = format_dart_literal_const(min);
+            constraints.push(Constraint {
+                valid: format!("{value} >= {min}"),
+                invalid: format!("{value} < {min}"),
+                message: format!("{name} must be at least {min}"),
+                const_safe: true,
+            });
+        }
+
+        if let Some(max) = &field.max {
+            let max = format_dart_literal_const(max);
+            constraints.push(Constraint {
+                valid: format!("{value} <= {max}"),
+                invalid: format!("{value} > {max}"),
+                message: format!("{name} must be at most {max}"),
+                const_safe: true,
+            });
+        }
+
+        if let Some(min_length) = &field.min_length {
+            constraints.push(Constraint {
+                valid: format!("{value}.length >= {min_length}"),
+                invalid: format!("{value}.length < {min_length}"),
+                message: format!(
+                    "{name} must be at least {} long",
+                    plural(min_length.value, "character")
+                ),
+                const_safe: true,
+            });
+        }
+
+        if let Some(max_length) = &field.max_length {
+            constraints.push(Constraint {
+                valid: format!("{value}.length <= {max_length}"),
+                invalid: format!("{value}.length > {max_length}"),
+                message: format!(
+                    "{name} must be at most {} long",
+                    plural(max_length.value, "character")
+                ),
+                const_safe: true,
+            });
+        }
+
+        if let Some(pattern) = &field.pattern {
+            let regex = dart_string_literal(pattern);
+            constraints.push(Constraint {
+                valid: format!("RegExp({regex}).hasMatch({value})"),
+                invalid: format!("!RegExp({regex}).hasMatch({value})"),
+                message: format!("{name} must match the pattern {}", pattern.as_str()),
+                const_safe: false,
+            });
+        }
+
+        // `length` on collections is not a constant expression, so these can't be asserted
+        if let Some(min_items) = &field.min_items {
+            constraints.push(Constraint {
+                valid: format!("{value}.length >= {min_items}"),
+                invalid: format!("{value}.length < {min_items}"),
+                message: format!(
+                    "{name} must have at least {}",
+                    plural(min_items.value, "item")
+                ),
+                const_safe: false,
+            });
+        }
+
+        if let Some(max_items) = &field.max_items {
+            constraints.push(Constraint {
+                valid: format!("{value}.length <= {max_items}"),
+                invalid: format!("{value}.length > {max_items}"),
+                message: format!(
+                    "{name} must have at most {}",
+                    plural(max_items.value, "item")
+                ),
+                const_safe: false,
+            });
+        }
+
+        constraints
+    }
+}
+
+fn plural(count: u64, word: &str) -> String {
+    match count {
+        1 => format!("1 {word}"),
+        _ => format!("{count} {word}s"),
+    }
+}
@@ -2,9 +2,7 @@ use super::*;
 
 impl Context {
     pub(super) fn codegen_enum(&self, buf: &mut String, enumeration: &Enum) -> std::fmt::Result {
-        if let Some(doc) = &enumeration.docs {
-            self.write_doc_comment(buf, doc)?;
-        }
+        self.write_type_docs(buf, enumeration.docs.as_ref(), &enumeration.examples)?;
 
         let default_annotations = self
             .library
@@ -21,7 +19,11 @@ impl Context {
         }
 
         let name = &enumeration.name;
-        writeln!(buf, "enum {name}")?;
+        let comparable = enumeration.comparable.as_ref().is_some_and(|c| c.value);
+        match comparable {
+            true => writeln!(buf, "enum {name} implements Comparable<{name}>")?,
+            false => writeln!(buf, "enum {name}")?,
+        }
 
         braced(buf, |out| {
             for variant in &enumeration.variants {
@@ -44,7 +46,7 @@ impl Context {
                         .json_value
                         .as_ref()
                         .map(format_dart_literal_const)
-                        .unwrap_or_else(|| format!("\"{}\"", variant.name));
+                        .unwrap_or_else(|| dart_string_literal(&variant.name));
 
                     let variant = &variant.name;
                     writeln!(out, "{value} => {name}.{variant},")?;
@@ -68,7 +70,7 @@ impl Context {
                         .json_value
                         .as_ref()
                         .map(format_dart_literal_const)
-                        .unwrap_or_else(|| format!("\"{}\"", variant.name));
+                        .unwrap_or_else(|| dart_string_literal(&variant.name));
 
                     write!(out, "{name}.{variant_name} => {value},")?;
                 }
@@ -90,6 +92,13 @@ impl Context {
                 self.generate_to_string_class(out, enumeration)?;
             }
 
+            if comparable {
+                writeln!(
+                    out,
+                    "@override\nint compareTo({name} other) => index.compareTo(other.index);"
+                )?;
+            }
+
             if let Some(extra_dart) = &enumeration.extra_dart {
                 writeln!(out, "{extra_dart}")?;
             }
@@ -107,7 +116,9 @@ impl Context {
                 let enum_name = &enumeration.name;
                 let variant_name = &variant.name;
 
-                writeln!(out, "{enum_name}.{variant_name} => \"{variant_name}\",")?;
+                let string = dart_string_literal(variant_name);
+
+                writeln!(out, "{enum_name}.{variant_name} => {string},")?;
             }
 
             Ok(())
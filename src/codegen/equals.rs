@@ -1,4 +1,4 @@
-use crate::context::{Ty, TyKind};
+use crate::context::{Ty, TyKind, record_fields};
 
 use super::*;
 
@@ -20,7 +20,11 @@ impl Context {
 
             writeln!(out, "if (identical(this, other)) {{ return true;  }}")?;
             writeln!(out, "if (other is! {class_name}) {{ return false; }}")?;
-            for field in &class.fields {
+            for field in class
+                .fields
+                .iter()
+                .filter(|f| self.library.compares_field(f))
+            {
                 self.generate_field_equals(out, field)?;
             }
             writeln!(out, "return true;")?;
@@ -35,41 +39,68 @@ impl Context {
         let ty = self.parse_ty(&field.ty).0.unwrap();
         let name = &field.name;
 
-        match ty.kind {
-            Simple(_) | Nullable(_) => {
-                writeln!(buf, "if ({name} != other.{name}) {{ return false; }}")
+        match &ty.kind {
+            Simple(_) | Generic { .. } | Nullable(_) | Record { .. } => {
+                write!(buf, "if (")?;
+                self.write_not_equals(buf, name, &format!("other.{name}"), &ty, 0)?;
+                writeln!(buf, ") {{ return false; }}")
             }
-            List(_) => {
+            List(inner) => {
                 writeln!(
                     buf,
                     "if ({name}.length != other.{name}.length) {{ return false; }}"
                 )?;
                 writeln!(buf, "for (var i = 0; i < {name}.length; i++)")?;
                 braced(buf, |out| {
-                    writeln!(out, "if ({name}[i] != other.{name}[i]) {{ return false; }}")
+                    write!(out, "if (")?;
+                    self.write_not_equals(
+                        out,
+                        &format!("{name}[i]"),
+                        &format!("other.{name}[i]"),
+                        inner,
+                        0,
+                    )?;
+                    writeln!(out, ") {{ return false; }}")
                 })
             }
-            Set(_) => {
+            Set(inner) => {
                 writeln!(
                     buf,
                     "if ({name}.length != other.{name}.length) {{ return false; }}"
                 )?;
                 writeln!(buf, "for (final elem in {name})")?;
                 braced(buf, |out| {
-                    writeln!(out, "if (!other.{name}.contains(elem)) {{ return false; }}")
+                    if self.compares_shallowly(inner) {
+                        return writeln!(
+                            out,
+                            "if (!other.{name}.contains(elem)) {{ return false; }}"
+                        );
+                    }
+
+                    write!(out, "if (!other.{name}.any((other0) => !(")?;
+                    self.write_not_equals(out, "elem", "other0", inner, 1)?;
+                    writeln!(out, "))) {{ return false; }}")
                 })
             }
-            Map { .. } => {
+            Map { value, .. } => {
                 writeln!(
                     buf,
                     "if ({name}.length != other.{name}.length) {{ return false; }}"
                 )?;
                 writeln!(buf, "for (final entry in {name}.entries)")?;
                 braced(buf, |out| {
-                    writeln!(
-                        out,
-                        "if (entry.value != other.{name}[entry.key]) {{ return false; }}"
-                    )
+                    write!(out, "if (")?;
+
+                    // `[]` returns null for missing keys, which can't be told apart from null values
+                    // and can't be compared as a collection or record
+                    let mut other = format!("other.{name}[entry.key]");
+                    if !matches!(value.kind, Simple(_) | Generic { .. }) {
+                        write!(out, "!other.{name}.containsKey(entry.key) || ")?;
+                        other = format!("({other} as {value})");
+                    }
+
+                    self.write_not_equals(out, "entry.value", &other, value, 0)?;
+                    writeln!(out, ") {{ return false; }}")
                 })
             }
         }?;
@@ -77,9 +108,148 @@ impl Context {
         Ok(())
     }
 
+    /// Writes a boolean expression that is true when `left` and `right` are not equal, respecting
+    /// the `equality` of external types. Collections are compared element by element, and nullable
+    /// values are only compared as their inner type once neither side is null. `depth` keeps the
+    /// names of closure parameters unique, since both `left` and `right` are used inside them
+    fn write_not_equals(
+        &self,
+        buf: &mut String,
+        left: &str,
+        right: &str,
+        ty: &Ty,
+        depth: usize,
+    ) -> std::fmt::Result {
+        let ident = match &ty.kind {
+            TyKind::Simple(ident) | TyKind::Generic { name: ident, .. } => ident,
+            TyKind::Record { positional, named } => {
+                return self.write_record_not_equals(buf, left, right, positional, named, depth);
+            }
+            TyKind::List(inner) => {
+                let i = format!("i{depth}");
+                write!(
+                    buf,
+                    "({left}.length != {right}.length || Iterable<int>.generate({left}.length).any(({i}) => "
+                )?;
+                self.write_not_equals(
+                    buf,
+                    &format!("{left}[{i}]"),
+                    &format!("{right}[{i}]"),
+                    inner,
+                    depth + 1,
+                )?;
+                return write!(buf, "))");
+            }
+            TyKind::Set(inner) if self.compares_shallowly(inner) => {
+                return write!(
+                    buf,
+                    "({left}.length != {right}.length || !{left}.every({right}.contains))"
+                );
+            }
+            // `contains` uses the elements' own `==`, so other equalities need a linear search
+            TyKind::Set(inner) => {
+                let (elem, other) = (format!("elem{depth}"), format!("other{depth}"));
+                write!(
+                    buf,
+                    "({left}.length != {right}.length || {left}.any(({elem}) => !{right}.any(({other}) => !("
+                )?;
+                self.write_not_equals(buf, &elem, &other, inner, depth + 1)?;
+                return write!(buf, "))))");
+            }
+            TyKind::Map { value, .. } => {
+                let entry = format!("entry{depth}");
+                write!(
+                    buf,
+                    "({left}.length != {right}.length || {left}.entries.any(({entry}) => !{right}.containsKey({entry}.key) || "
+                )?;
+                self.write_not_equals(
+                    buf,
+                    &format!("{entry}.value"),
+                    &format!("({right}[{entry}.key] as {value})"),
+                    value,
+                    depth + 1,
+                )?;
+                return write!(buf, "))");
+            }
+            TyKind::Nullable(inner) => match &inner.kind {
+                TyKind::Simple(ident) | TyKind::Generic { name: ident, .. } => ident,
+                _ => {
+                    write!(
+                        buf,
+                        "({left} == null || {right} == null ? {left} != {right} : "
+                    )?;
+                    self.write_not_equals(
+                        buf,
+                        &format!("({left} as {inner})"),
+                        &format!("({right} as {inner})"),
+                        inner,
+                        depth,
+                    )?;
+                    return write!(buf, ")");
+                }
+            },
+        };
+
+        match self.library.equality_for(ident) {
+            Equality::Shallow => write!(buf, "{left} != {right}"),
+            Equality::Deep => write!(
+                buf,
+                "!const DeepCollectionEquality().equals({left}, {right})"
+            ),
+            Equality::Identity => write!(buf, "!identical({left}, {right})"),
+        }
+    }
+
+    /// Whether values of `ty` are compared with their own `==`, so that `Set.contains` finds them
+    fn compares_shallowly(&self, ty: &Ty) -> bool {
+        let ty = match &ty.kind {
+            TyKind::Nullable(inner) => inner,
+            _ => ty,
+        };
+
+        match &ty.kind {
+            TyKind::Simple(ident) | TyKind::Generic { name: ident, .. } => {
+                self.library.equality_for(ident) == Equality::Shallow
+            }
+            _ => false,
+        }
+    }
+
+    /// Records are compared field by field, rather than with their own `==`, so that their fields
+    /// are compared the same way as class fields
+    fn write_record_not_equals(
+        &self,
+        buf: &mut String,
+        left: &str,
+        right: &str,
+        positional: &[Ty],
+        named: &[(String, Ty)],
+        depth: usize,
+    ) -> std::fmt::Result {
+        let fields = record_fields(positional, named);
+        if fields.is_empty() {
+            return write!(buf, "false");
+        }
+
+        write!(buf, "(")?;
+        for (i, (getter, ty)) in fields.into_iter().enumerate() {
+            if i > 0 {
+                write!(buf, " || ")?;
+            }
+
+            let (left, right) = (format!("{left}.{getter}"), format!("{right}.{getter}"));
+            self.write_not_equals(buf, &left, &right, ty, depth)?;
+        }
+        write!(buf, ")")
+    }
+
     fn generate_hash_code(&self, buf: &mut String, class: &Class) -> std::fmt::Result {
         writeln!(buf, "@override\n int get hashCode => Object.hashAll([")?;
-        for field in &class.fields {
+        for field in class
+            .fields
+            .iter()
+            .filter(|f| self.library.compares_field(f))
+        {
             let ty = self.parse_ty(&field.ty).0.unwrap();
             self.write_hash_for_field(buf, &field.name, &ty)?;
             writeln!(buf, ",")?;
@@ -89,23 +259,55 @@ impl Context {
         Ok(())
     }
 
-    #[allow(clippy::only_used_in_recursion)]
     fn write_hash_for_field(&self, buf: &mut String, expr: &str, ty: &Ty) -> std::fmt::Result {
         match &ty.kind {
-            TyKind::Simple(_) => write!(buf, "{expr}.hashCode")?,
+            TyKind::Simple(ident) | TyKind::Generic { name: ident, .. } => {
+                match self.library.equality_for(ident) {
+                    Equality::Shallow => write!(buf, "{expr}.hashCode")?,
+                    Equality::Deep => write!(buf, "const DeepCollectionEquality().hash({expr})")?,
+                    Equality::Identity => write!(buf, "identityHashCode({expr})")?,
+                }
+            }
             TyKind::List(inner) | TyKind::Set(inner) => {
                 write!(buf, "Object.hashAll({expr}.map((elem) => ")?;
                 self.write_hash_for_field(buf, "elem", inner)?;
                 write!(buf, "))")?
             }
-            TyKind::Map { value , .. } => {
-                write!(buf, "Object.hashAll({expr}.entries.expand((entry) => [entry.key, ")?;
+            TyKind::Map { value, .. } => {
+                write!(
+                    buf,
+                    "Object.hashAll({expr}.entries.expand((entry) => [entry.key, "
+                )?;
                 self.write_hash_for_field(buf, "entry.value", value)?;
                 write!(buf, "]))")?
             }
-            TyKind::Nullable(_) => {
-                // TODO(cameron): this isn't quite right
-                writeln!(buf, "{expr}?.hashCode")?
+            TyKind::Record { positional, named } => {
+                write!(buf, "Object.hashAll([")?;
+                for (getter, ty) in record_fields(positional, named) {
+                    self.write_hash_for_field(buf, &format!("{expr}.{getter}"), ty)?;
+                    write!(buf, ", ")?;
+                }
+                write!(buf, "])")?
+            }
+            // both `identityHashCode` and `DeepCollectionEquality.hash` accept null
+            TyKind::Nullable(inner)
+                if matches!(
+                    &inner.kind,
+                    TyKind::Simple(ident) | TyKind::Generic { name: ident, .. }
+                        if self.library.equality_for(ident) != Equality::Shallow
+                ) =>
+            {
+                self.write_hash_for_field(buf, expr, inner)?
+            }
+            TyKind::Nullable(inner)
+                if matches!(inner.kind, TyKind::Simple(_) | TyKind::Generic { .. }) =>
+            {
+                write!(buf, "{expr}?.hashCode")?
+            }
+            TyKind::Nullable(inner) => {
+                write!(buf, "({expr} == null ? 0 : ")?;
+                self.write_hash_for_field(buf, &format!("({expr} as {inner})"), inner)?;
+                write!(buf, ")")?
             }
         }
 
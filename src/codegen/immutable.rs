@@ -9,9 +9,7 @@ impl Context {
         class: &Class,
         superclass: Option<&Union>,
     ) -> std::fmt::Result {
-        if let Some(source) = &class.docs {
-            self.write_doc_comment(buf, source)?;
-        }
+        self.write_type_docs(buf, class.docs.as_ref(), &class.examples)?;
         if let Some(annotations) = &class.annotations {
             writeln!(buf, "{annotations}")?;
         }
@@ -24,10 +22,29 @@ impl Context {
         {
             writeln!(buf, "{annotations}")?;
         }
-        write!(buf, "final class {} ", class.name)?;
-        if let Some(superclass) = &superclass {
-            write!(buf, "extends {} ", superclass.name)?;
+        let modifier = self
+            .library
+            .class_modifier(class)
+            .map_or(ClassModifier::Final, |modifier| modifier.value);
+        let extends = match superclass {
+            Some(superclass) => Some(superclass.name.as_str()),
+            None => class.extends.as_ref().map(|extends| extends.as_str()),
+        };
+        let mut implements = class.implements.clone();
+        if class.ordering.is_some() {
+            implements.push(SpannedScalar {
+                value: format!("Comparable<{}>", class.name),
+                span: class.name.span,
+            });
         }
+        class_header(
+            buf,
+            modifier.prefix(),
+            &class.name,
+            extends,
+            &class.with,
+            &implements,
+        )?;
 
         braced(buf, |out| {
             for field in &class.fields {
@@ -57,7 +74,7 @@ impl Context {
                         (Some(_), Some(_)) => unreachable!("checked in validation"),
                         (None, None) => {}
                         (Some(defaults_to), None) => {
-                            let dart = format_dart_literal_const(defaults_to);
+                            let dart = self.format_default(field, defaults_to);
                             if dart != "null" {
                                 writeln!(out, "= {dart}")?;
                             }
@@ -73,9 +90,14 @@ impl Context {
                 writeln!(out, "}})")?;
             }
 
-            match superclass {
-                Some(_) => writeln!(out, " : super();")?,
-                None => writeln!(out, ";")?,
+            let mut initializers = self.constraint_asserts(class);
+            if superclass.is_some() {
+                initializers.push("super()".to_string());
+            }
+
+            match initializers.is_empty() {
+                true => writeln!(out, ";")?,
+                false => writeln!(out, " : {};", initializers.join(", "))?,
             }
 
             writeln!(out)?;
@@ -97,11 +119,19 @@ impl Context {
 
             writeln!(out)?;
 
+            write_rebuild(out, &class.name, superclass.is_some())?;
+
+            writeln!(out)?;
+
             self.generate_to_json(out, class, superclass)?;
-            self.generate_from_json(out, class)?;
+            self.generate_from_json(out, class, superclass)?;
 
             writeln!(out)?;
 
+            if self.has_constraints(class) {
+                self.generate_validate(out, class, false)?;
+            }
+
             let generate_to_string = self
                 .library
                 .defaults
@@ -126,6 +156,10 @@ impl Context {
                 self.generate_equals_and_hash(out, class)?;
             }
 
+            if let Some(ordering) = &class.ordering {
+                self.generate_compare_to(out, class, ordering)?;
+            }
+
             if let Some(extra) = &class.extra_dart {
                 writeln!(out, "{extra}")?;
                 writeln!(out)?;
@@ -138,15 +172,26 @@ impl Context {
     }
 
     fn generate_to_string_enum(&self, buf: &mut String, class: &Class) -> std::fmt::Result {
-        writeln!(buf, "@override\nString toString() => \"{}(\"", class.name)?;
-        for (index, field) in class.fields.iter().enumerate() {
+        writeln!(
+            buf,
+            "@override\nString toString() => {}",
+            dart_string_literal(&format!("{}(", class.name))
+        )?;
+        let fields: Vec<_> = class
+            .fields
+            .iter()
+            .map(|field| (field, self.library.to_string_mode_for(field)))
+            .filter(|(_, mode)| *mode != ToStringMode::Omit)
+            .collect();
+        for (index, (field, mode)) in fields.iter().enumerate() {
             let name = &field.name;
-            let trailing_comma = if index == class.fields.len() - 1 {
-                ""
-            } else {
-                ", "
+            let trailing_comma = if index == fields.len() - 1 { "" } else { ", " };
+            let label = escape_dart_string(name);
+            let value = match mode {
+                ToStringMode::Redacted => "<redacted>".to_string(),
+                _ => format!("${name}"),
             };
-            writeln!(buf, "\"{name}: ${name}{trailing_comma}\"")?;
+            writeln!(buf, "\"{label}: {value}{trailing_comma}\"")?;
         }
         writeln!(buf, "\")\";")?;
         Ok(())
@@ -165,21 +210,27 @@ impl Context {
 
         writeln!(buf, "static {class_name}Builder builder({{")?;
         for field in &class.fields {
-            let required_kw = if field.defaults_to.is_none() && field.defaults_to_dart.is_none() {
-                "required"
-            } else {
-                ""
-            };
+            let partial = self.is_partial_builder_field(class, field);
+            // partial builders don't require nullable fields either, so they can start empty
+            let optional = self.library.builder_mode_for(class) == BuilderMode::Partial
+                && (partial || self.field_is_nullable(field));
+            let required_kw =
+                if field.defaults_to.is_none() && field.defaults_to_dart.is_none() && !optional {
+                    "required"
+                } else {
+                    ""
+                };
 
             let field_ty = &field.ty;
             let field_name = &field.name;
+            let nullable = if partial { "?" } else { "" };
 
-            write!(buf, "{required_kw} {field_ty} {field_name}")?;
+            write!(buf, "{required_kw} {field_ty}{nullable} {field_name}")?;
             match (&field.defaults_to, &field.defaults_to_dart) {
                 (Some(_), Some(_)) => unreachable!("checked in validation"),
                 (None, None) => {}
                 (Some(defaults_to), None) => {
-                    let dart = format_dart_literal_const(defaults_to);
+                    let dart = self.format_default(field, defaults_to);
                     if dart != "null" {
                         writeln!(buf, " = {dart}")?;
                     }
@@ -195,9 +246,16 @@ impl Context {
         writeln!(buf, "}}) => {class_name}Builder(")?;
         for field in &class.fields {
             let field_name = &field.name;
+            let mut ty = self.parse_ty(&field.ty).0.unwrap();
+            if self.is_partial_builder_field(class, field) {
+                ty = Ty {
+                    span: ty.span,
+                    kind: TyKind::Nullable(Box::new(ty)),
+                };
+            }
 
             write!(buf, "{field_name}: ")?;
-            self.write_to_builder_expr(buf, field_name, &self.parse_ty(&field.ty).0.unwrap())?;
+            self.write_to_builder_expr(buf, field_name, &ty)?;
             writeln!(buf, ",")?;
         }
         writeln!(buf, ");")?;
@@ -205,12 +263,17 @@ impl Context {
         Ok(())
     }
 
-    fn write_to_builder_expr(&self, buf: &mut String, expr: &str, ty: &Ty) -> std::fmt::Result {
+    pub(super) fn write_to_builder_expr(
+        &self,
+        buf: &mut String,
+        expr: &str,
+        ty: &Ty,
+    ) -> std::fmt::Result {
         match &ty.kind {
             TyKind::Simple(ident) if self.library.type_has_builder(ident) => {
                 write!(buf, "{expr}.toBuilder()")?;
             }
-            TyKind::Simple(_) => {
+            TyKind::Simple(_) | TyKind::Generic { .. } => {
                 write!(buf, "{expr}")?;
             }
             TyKind::List(inner) => {
@@ -228,6 +291,11 @@ impl Context {
                 self.write_to_builder_expr(buf, "value", value)?;
                 write!(buf, "))")?;
             }
+            TyKind::Record { positional, named } => {
+                write_record_literal(buf, positional, named, |buf, _, getter, ty| {
+                    self.write_to_builder_expr(buf, &format!("{expr}.{getter}"), ty)
+                })?;
+            }
             TyKind::Nullable(inner) => {
                 write!(buf, "{expr} == null ? null : ")?;
                 self.write_to_builder_expr(buf, &format!("({expr} as {inner})"), inner)?;
@@ -1,4 +1,6 @@
-use crate::context::{Ty, TyKind};
+use std::borrow::Cow;
+
+use crate::context::{Ty, TyKind, record_fields};
 
 use super::*;
 
@@ -14,116 +16,421 @@ impl Context {
         }
         writeln!(buf, "Map<String, dynamic> toJson() => {{")?;
 
+        let Some(union) = superclass else {
+            self.write_json_fields(buf, class)?;
+            writeln!(buf, "}};")?;
+            return Ok(());
+        };
+
+        let discriminant_key = self.library.discriminant_key_for(union);
+        let discriminant_value = self.library.discriminant_value_for(union, class);
+
+        match self.library.json_representation_for(union) {
+            JsonRepresentation::Internal => {
+                self.write_json_fields(buf, class)?;
+
+                let discriminants: Vec<_> = self
+                    .library
+                    .discriminants_for(union, class)
+                    .into_iter()
+                    .map(|(key, value)| format!("{}: {value}", dart_string_literal(key)))
+                    .collect();
+
+                writeln!(buf, "{}", discriminants.join(", "))?;
+            }
+            JsonRepresentation::Adjacent => {
+                let content_key = self.library.json_content_key_for(union);
+
+                writeln!(
+                    buf,
+                    "{}: {discriminant_value},",
+                    dart_string_literal(discriminant_key)
+                )?;
+                writeln!(buf, "{}: {{", dart_string_literal(content_key))?;
+                self.write_json_fields(buf, class)?;
+                writeln!(buf, "}}")?;
+            }
+            JsonRepresentation::External => {
+                writeln!(buf, "{discriminant_value}: {{")?;
+                self.write_json_fields(buf, class)?;
+                writeln!(buf, "}}")?;
+            }
+            JsonRepresentation::Untagged => {
+                self.write_json_fields(buf, class)?;
+            }
+        }
+
+        writeln!(buf, "}};")?;
+
+        Ok(())
+    }
+
+    fn write_json_fields(&self, buf: &mut String, class: &Class) -> std::fmt::Result {
         for field in &class.fields {
             let json_key = self.library.json_key_for(class, field);
             let field_name = &field.name;
 
             if let Some(to_json) = &field.to_json {
-                writeln!(buf, "\"{json_key}\": ")?;
+                writeln!(buf, "{}: ", dart_string_literal(&json_key))?;
                 writeln!(buf, "// ignore: unnecessary_parenthesis")?;
                 write!(buf, "({to_json})({field_name}),")?;
             } else {
-                write!(buf, "\"{json_key}\": ")?;
+                write!(buf, "{}: ", dart_string_literal(&json_key))?;
                 let ty = self.parse_ty(&field.ty).0.unwrap();
-                self.write_expr_to_json(buf, field_name, &ty)?;
+                let records = self.library.record_representation_for(field);
+                self.write_expr_to_json(buf, field_name, &ty, records)?;
                 writeln!(buf, ",")?;
             }
         }
 
-        if let Some(union) = superclass {
-            let discriminant_key = self.library.discriminant_key_for(union);
-            let discriminant_value = self.library.discriminant_value_for(union, class);
+        Ok(())
+    }
 
-            writeln!(buf, "\"{discriminant_key}\": {discriminant_value}",)?;
-        }
+    pub(super) fn generate_from_json(
+        &self,
+        buf: &mut String,
+        class: &Class,
+        superclass: Option<&Union>,
+    ) -> std::fmt::Result {
+        let class_name = &class.name;
 
-        writeln!(buf, "}};")?;
+        // adjacently and externally tagged unions nest the fields inside another object
+        let content_key = match superclass {
+            Some(union) if !class.fields.is_empty() => {
+                match self.library.json_representation_for(union) {
+                    JsonRepresentation::Adjacent => Some(dart_string_literal(
+                        self.library.json_content_key_for(union),
+                    )),
+                    JsonRepresentation::External => Some(
+                        self.library
+                            .discriminant_value_for(union, class)
+                            .into_owned(),
+                    ),
+                    JsonRepresentation::Internal | JsonRepresentation::Untagged => None,
+                }
+            }
+            _ => None,
+        };
 
-        Ok(())
-    }
+        let validate = self.validates_from_json(class);
+
+        if content_key.is_none() && !validate {
+            writeln!(
+                buf,
+                "factory {class_name}.fromJson(Map<String, dynamic> json) => {class_name}("
+            )?;
+
+            for field in &class.fields {
+                write!(buf, "{}: ", field.name)?;
+                self.write_field_from_json(buf, class, field, "json")?;
+                writeln!(buf, ",")?;
+            }
+
+            writeln!(buf, ");")?;
+
+            return Ok(());
+        }
 
-    pub(super) fn generate_from_json(&self, buf: &mut String, class: &Class) -> std::fmt::Result {
         writeln!(
             buf,
-            "factory {0}.fromJson(Map<String, dynamic> json) => {0}(",
-            class.name
+            "factory {class_name}.fromJson(Map<String, dynamic> json) {{"
         )?;
 
-        for field in &class.fields {
-            let json_key = self.library.json_key_for(class, field);
-            let field_ty = &field.ty;
-            let field_name = &field.name;
-            let expr = format!("json[\"{json_key}\"]");
-            let ty = self.parse_ty(field_ty).0.unwrap();
+        let map = match &content_key {
+            None => "json",
+            Some(content_key) => {
+                writeln!(
+                    buf,
+                    "final content = json[{content_key}] as Map<String, dynamic>;"
+                )?;
+                "content"
+            }
+        };
 
-            write!(buf, "{field_name}: ")?;
+        if !validate {
+            writeln!(buf, "return {class_name}(")?;
 
-            // if a field has a default, we always check for null and then return the default if
-            // null
-            if let Some(defaults_to) = &field.defaults_to {
-                let default = format_dart_literal_const(defaults_to);
-                write!(buf, "{expr} == null ? {default} : ")?;
-            } else if let Some(defaults_to_dart) = &field.defaults_to_dart {
-                write!(buf, "{expr} == null ? {defaults_to_dart} : ")?;
+            for field in &class.fields {
+                write!(buf, "{}: ", field.name)?;
+                self.write_field_from_json(buf, class, field, map)?;
+                writeln!(buf, ",")?;
             }
 
-            if let Some(from_json) = &field.from_json {
-                writeln!(buf)?;
-                writeln!(buf, "// ignore: unnecessary_parenthesis")?;
-                writeln!(buf, "({from_json})({expr})")?;
-            } else {
-                self.write_expr_from_json(buf, &expr, &ty)?;
-            }
-            writeln!(buf, ",")?;
+            writeln!(buf, ");")?;
+            writeln!(buf, "}}")?;
+
+            return Ok(());
         }
 
+        // decode every field into a local first, so that constraints can be checked before
+        // calling the constructor (which would otherwise fail with an `AssertionError`)
+        for field in &class.fields {
+            write!(buf, "final ${} = ", field.name)?;
+            self.write_field_from_json(buf, class, field, map)?;
+            writeln!(buf, ";")?;
+        }
+
+        write!(buf, "final violations = ")?;
+        self.write_violations(buf, class, false, |name| format!("${name}"))?;
+        writeln!(buf, ";")?;
+
+        writeln!(buf, "if (violations.isNotEmpty)")?;
+        braced(buf, |out| {
+            writeln!(
+                out,
+                r#"throw ArgumentError.value(json, "json", violations.join(", "));"#
+            )
+        })?;
+
+        writeln!(buf, "return {class_name}(")?;
+        for field in &class.fields {
+            writeln!(buf, "{0}: ${0},", field.name)?;
+        }
         writeln!(buf, ");")?;
+        writeln!(buf, "}}")?;
+
+        Ok(())
+    }
+
+    /// Writes the expression that decodes `field` from the JSON object `map`
+    fn write_field_from_json(
+        &self,
+        buf: &mut String,
+        class: &Class,
+        field: &Field,
+        map: &str,
+    ) -> std::fmt::Result {
+        let json_key = self.library.json_key_for(class, field);
+        let expr = format!("{map}[{}]", dart_string_literal(&json_key));
+        let ty = self.parse_ty(&field.ty).0.unwrap();
+
+        // if a field has a default, we always check for null and then return the default if
+        // null
+        if let Some(defaults_to) = &field.defaults_to {
+            let default = self.format_default(field, defaults_to);
+            write!(buf, "{expr} == null ? {default} : ")?;
+        } else if let Some(defaults_to_dart) = &field.defaults_to_dart {
+            write!(buf, "{expr} == null ? {defaults_to_dart} : ")?;
+        }
+
+        if let Some(from_json) = &field.from_json {
+            writeln!(buf)?;
+            writeln!(buf, "// ignore: unnecessary_parenthesis")?;
+            writeln!(buf, "({from_json})({expr})")?;
+        } else {
+            let records = self.library.record_representation_for(field);
+            self.write_expr_from_json(buf, &expr, &ty, records)?;
+        }
 
         Ok(())
     }
 
+    /// Generates the `fromJson` factory of a union, which decides which member to decode
+    pub(super) fn generate_union_from_json(
+        &self,
+        buf: &mut String,
+        union: &Union,
+    ) -> std::fmt::Result {
+        let union_name = &union.name;
+
+        match self.library.json_representation_for(union) {
+            JsonRepresentation::Internal | JsonRepresentation::Adjacent => {
+                let discriminant_key = self.library.discriminant_key_for(union);
+                writeln!(
+                    buf,
+                    "factory {union_name}.fromJson(Map<String, dynamic> json) => switch (json[{}]) {{",
+                    dart_string_literal(discriminant_key),
+                )?;
+
+                for entry in self.library.discriminant_entries(union) {
+                    let name = entry.name;
+                    let discriminant_value = entry.value;
+
+                    writeln!(buf, "{discriminant_value} => {name}.fromJson(json),")?;
+                }
+                writeln!(
+                    buf,
+                    r#"final other => throw ArgumentError("unknown discriminant: $other"),"#
+                )?;
+
+                writeln!(buf, "}};")?;
+            }
+            JsonRepresentation::External => {
+                writeln!(
+                    buf,
+                    "factory {union_name}.fromJson(Map<String, dynamic> json) => switch (json.keys.singleOrNull) {{"
+                )?;
+
+                for class in &union.classes {
+                    let name = &class.name;
+                    let discriminant_value = self.library.discriminant_value_for(union, class);
+
+                    writeln!(buf, "{discriminant_value} => {name}.fromJson(json),")?;
+                }
+                writeln!(
+                    buf,
+                    r#"_ => throw ArgumentError("expected a single key naming a member of {}, found: ${{json.keys}}"),"#,
+                    escape_dart_string(union_name),
+                )?;
+
+                writeln!(buf, "}};")?;
+            }
+            JsonRepresentation::Untagged => {
+                writeln!(
+                    buf,
+                    "factory {union_name}.fromJson(Map<String, dynamic> json) {{"
+                )?;
+
+                for (class, keys) in self.untagged_members(union) {
+                    let name = &class.name;
+
+                    if keys.is_empty() {
+                        writeln!(buf, "return {name}.fromJson(json);")?;
+                        writeln!(buf, "}}")?;
+                        return Ok(());
+                    }
+
+                    let conditions: Vec<_> = keys
+                        .iter()
+                        .map(|key| format!("json.containsKey({})", dart_string_literal(key)))
+                        .collect();
+
+                    writeln!(buf, "if ({})", conditions.join(" && "))?;
+                    braced(buf, |out| writeln!(out, "return {name}.fromJson(json);"))?;
+                }
+
+                writeln!(
+                    buf,
+                    r#"throw ArgumentError("json does not match any member of {}: $json");"#,
+                    escape_dart_string(union_name),
+                )?;
+                writeln!(buf, "}}")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The members of an untagged union, along with the keys they require, in the order they
+    /// are checked. Members that require more keys are checked first, so that a member whose keys
+    /// are a subset of another member's keys doesn't shadow it
+    pub(crate) fn untagged_members<'lib>(
+        &'lib self,
+        union: &'lib Union,
+    ) -> Vec<(&'lib Class, Vec<Cow<'lib, str>>)> {
+        let mut members: Vec<_> = union
+            .classes
+            .iter()
+            .map(|class| (class, self.required_json_keys(class)))
+            .collect();
+
+        members.sort_by_key(|(_, keys)| std::cmp::Reverse(keys.len()));
+
+        members
+    }
+
+    /// The keys that must be present for `fromJson` to succeed, i.e. the keys of fields which
+    /// have no default and are not nullable
+    pub(crate) fn required_json_keys<'lib>(&'lib self, class: &'lib Class) -> Vec<Cow<'lib, str>> {
+        class
+            .fields
+            .iter()
+            .filter(|field| field.defaults_to.is_none() && field.defaults_to_dart.is_none())
+            .filter(|field| {
+                let (ty, _) = self.parse_ty(&field.ty);
+                !matches!(ty.map(|ty| ty.kind), Some(TyKind::Nullable(_)))
+            })
+            .map(|field| self.library.json_key_for(class, field))
+            .collect()
+    }
+
     /// Some types need special handling to convert to JSON. The important cases are:
     /// - trivial values - Strings, ints, etc, - these are just `expr`
     /// - types we have generated - these are {expr}.toJson()
+    /// - external types with a `to-json` - these are ({to_json})({expr})
     /// - lists and sets are iterated over, then this function is applied to the inner expr, then
     ///   collected
-    fn write_expr_to_json(&self, buf: &mut String, expr: &str, ty: &Ty) -> std::fmt::Result {
+    /// - records become a list or a map, depending on `records`
+    /// - nullable values are checked for null, unless they are already valid JSON
+    fn write_expr_to_json(
+        &self,
+        buf: &mut String,
+        expr: &str,
+        ty: &Ty,
+        records: RecordRepresentation,
+    ) -> std::fmt::Result {
         match &ty.kind {
             // types generated by us
             TyKind::Simple(ident) if self.type_generated_by_us(ident) => {
                 write!(buf, "{expr}.toJson()")?
             }
-            // trivial types
-            TyKind::Simple(_) => write!(buf, "{expr}")?,
+            // external types with a custom conversion
+            TyKind::Simple(ident) | TyKind::Generic { name: ident, .. }
+                if self.external_to_json(ident).is_some() =>
+            {
+                let to_json = self.external_to_json(ident).unwrap();
+                writeln!(buf)?;
+                writeln!(buf, "// ignore: unnecessary_parenthesis")?;
+                write!(buf, "({to_json})({expr})")?
+            }
+            // trivial types, and generic types which validation has checked are trivial
+            TyKind::Simple(_) | TyKind::Generic { .. } => write!(buf, "{expr}")?,
             TyKind::List(inner) | TyKind::Set(inner) => {
                 write!(buf, "{expr}.map((inner) => ")?;
-                self.write_expr_to_json(buf, "inner", inner)?;
+                self.write_expr_to_json(buf, "inner", inner, records)?;
                 write!(buf, ").toList()")?
             }
-            TyKind::Map { value, .. } => {
-                // we've already checked that `key` is a string
-                //
-                write!(buf, "{expr}.map((key, value) => MapEntry(key, ")?;
-                self.write_expr_to_json(buf, "value", value)?;
+            TyKind::Map { key, value } => {
+                write!(buf, "{expr}.map((key, value) => MapEntry(")?;
+                self.write_key_to_json(buf, "key", key)?;
+                write!(buf, ", ")?;
+                self.write_expr_to_json(buf, "value", value, records)?;
                 write!(buf, "))")?;
             }
-            // TODO(cameron): this isn't really correct, but it works in common cases (e.g.
-            // nullable strings, etc.)
-            TyKind::Nullable(inner) => {
-                match &inner.kind {
-                    TyKind::Simple(ident) => match self.type_generated_by_us(ident) {
-                        true => write!(buf, "{expr}?.toJson()")?,
-                        false => write!(buf, "{expr}")?,
-                    },
-                    _ => self.write_expr_to_json(buf, expr, inner)?,
-                };
-            }
+            TyKind::Record { positional, named } => match records {
+                RecordRepresentation::Array => {
+                    write!(buf, "<dynamic>[")?;
+                    for (getter, ty) in record_fields(positional, named) {
+                        self.write_expr_to_json(buf, &format!("{expr}.{getter}"), ty, records)?;
+                        write!(buf, ", ")?;
+                    }
+                    write!(buf, "]")?
+                }
+                RecordRepresentation::Object => {
+                    write!(buf, "<String, dynamic>{{")?;
+                    for (getter, ty) in record_fields(positional, named) {
+                        write!(buf, "{}: ", dart_string_literal(&getter))?;
+                        self.write_expr_to_json(buf, &format!("{expr}.{getter}"), ty, records)?;
+                        write!(buf, ", ")?;
+                    }
+                    write!(buf, "}}")?
+                }
+            },
+            // values that are already valid JSON can be passed through, even if they are null.
+            // Everything else is checked for null first, so that the conversion of the inner type
+            // only ever sees non-null values
+            TyKind::Nullable(inner) => match &inner.kind {
+                TyKind::Simple(ident) if self.type_generated_by_us(ident) => {
+                    write!(buf, "{expr}?.toJson()")?
+                }
+                _ if self.is_json_value(inner) => write!(buf, "{expr}")?,
+                _ => {
+                    write!(buf, "{expr} == null ? null : ")?;
+                    self.write_expr_to_json(buf, &format!("({expr} as {inner})"), inner, records)?
+                }
+            },
         }
 
         Ok(())
     }
 
-    fn write_expr_from_json(&self, buf: &mut String, expr: &str, ty: &Ty) -> std::fmt::Result {
+    fn write_expr_from_json(
+        &self,
+        buf: &mut String,
+        expr: &str,
+        ty: &Ty,
+        records: RecordRepresentation,
+    ) -> std::fmt::Result {
         match &ty.kind {
             // types generated by us
             TyKind::Simple(ident) if self.type_generated_by_us(ident) => {
@@ -133,41 +440,136 @@ impl Context {
                     write!(buf, "{ident}.fromJson({expr} as Map<String, dynamic>)")?
                 }
             }
+            // external types with a custom conversion
+            TyKind::Simple(ident) | TyKind::Generic { name: ident, .. }
+                if self.external_from_json(ident).is_some() =>
+            {
+                let from_json = self.external_from_json(ident).unwrap();
+                writeln!(buf)?;
+                writeln!(buf, "// ignore: unnecessary_parenthesis")?;
+                write!(buf, "({from_json})({expr})")?
+            }
             // trivial types
             TyKind::Simple(ident) => write!(buf, "{expr} as {ident}")?,
+            TyKind::Generic { .. } => write!(buf, "{expr} as {ty}")?,
             TyKind::List(inner) => {
                 write!(buf, "({expr} as List<dynamic>).map<{inner}>((inner) => ")?;
-                self.write_expr_from_json(buf, "inner", inner)?;
+                self.write_expr_from_json(buf, "inner", inner, records)?;
                 write!(buf, ").toList()")?
             }
             TyKind::Set(inner) => {
                 write!(buf, "({expr} as List<dynamic>).map<{inner}>((inner) => ")?;
-                self.write_expr_from_json(buf, "inner", inner)?;
+                self.write_expr_from_json(buf, "inner", inner, records)?;
                 write!(buf, ").toSet()")?
             }
-            TyKind::Map { value, .. } => {
-                // we've already checked that `key` is a string
-                //
+            TyKind::Map { key, value } => {
                 write!(
                     buf,
-                    "({expr} as Map).map<String, {value}>((key, value) => MapEntry(key as String, "
+                    "({expr} as Map).map<{key}, {value}>((key, value) => MapEntry("
                 )?;
-                self.write_expr_from_json(buf, "value", value)?;
+                self.write_key_from_json(buf, "key", key)?;
+                write!(buf, ", ")?;
+                self.write_expr_from_json(buf, "value", value, records)?;
                 write!(buf, "))")?;
             }
-            // TODO(cameron): rethink this - there is special handling for top-level nulls in the
-            // fromJson code
+            TyKind::Record { positional, named } => {
+                write_record_literal(buf, positional, named, |buf, i, getter, ty| {
+                    let value = match records {
+                        RecordRepresentation::Array => format!("({expr} as List<dynamic>)[{i}]"),
+                        RecordRepresentation::Object => format!(
+                            "({expr} as Map<String, dynamic>)[{}]",
+                            dart_string_literal(getter)
+                        ),
+                    };
+                    self.write_expr_from_json(buf, &value, ty, records)
+                })?
+            }
+            // `expr` is `dynamic`, so the inner type can decode it as it is once it isn't null
             TyKind::Nullable(inner) => {
                 write!(buf, "{expr} == null ? null : ")?;
-                self.write_expr_from_json(buf, expr, inner)?;
+                self.write_expr_from_json(buf, expr, inner, records)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// JSON object keys are always strings, so map keys are converted to and from strings. The
+    /// key types allowed here are checked during validation:
+    /// - `String` keys are used as-is
+    /// - `int` and `double` keys use `toString` and `parse`
+    /// - enum keys use their (string) JSON values
+    /// - external types use their `to-json` and `from-json`, which must produce a string
+    fn write_key_to_json(&self, buf: &mut String, expr: &str, key: &Ty) -> std::fmt::Result {
+        let TyKind::Simple(ident) = &key.kind else {
+            unreachable!("map keys are checked in validation");
+        };
+
+        match ident.as_str() {
+            "String" => write!(buf, "{expr}")?,
+            "int" | "double" => write!(buf, "{expr}.toString()")?,
+            _ if self.type_generated_by_us(ident) => write!(buf, "{expr}.toJson() as String")?,
+            _ => {
+                let to_json = self
+                    .external_to_json(ident)
+                    .expect("map keys are checked in validation");
+                writeln!(buf)?;
+                writeln!(buf, "// ignore: unnecessary_parenthesis")?;
+                write!(buf, "({to_json})({expr}) as String")?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_key_from_json(&self, buf: &mut String, expr: &str, key: &Ty) -> std::fmt::Result {
+        let TyKind::Simple(ident) = &key.kind else {
+            unreachable!("map keys are checked in validation");
+        };
+
+        match ident.as_str() {
+            "String" => write!(buf, "{expr} as String")?,
+            "int" | "double" => write!(buf, "{ident}.parse({expr} as String)")?,
+            _ if self.type_generated_by_us(ident) => write!(buf, "{ident}.fromJson({expr})")?,
+            _ => {
+                let from_json = self
+                    .external_from_json(ident)
+                    .expect("map keys are checked in validation");
+                writeln!(buf)?;
+                writeln!(buf, "// ignore: unnecessary_parenthesis")?;
+                write!(buf, "({from_json})({expr})")?
             }
         }
 
         Ok(())
     }
 
+    /// Whether values of `ty` are written to JSON as they are, without any conversion
+    fn is_json_value(&self, ty: &Ty) -> bool {
+        match &ty.kind {
+            TyKind::Simple(ident) => {
+                !self.type_generated_by_us(ident) && self.external_to_json(ident).is_none()
+            }
+            TyKind::Generic { name, .. } => self.external_to_json(name).is_none(),
+            _ => false,
+        }
+    }
+
     /// All types generated by this tool have `toJson` and `fromJson` functions.
     fn type_generated_by_us(&self, type_name: &str) -> bool {
         self.library.type_names().any(|s| **s == type_name)
     }
+
+    fn external_to_json(&self, type_name: &str) -> Option<&str> {
+        let external = self.library.external_type(type_name)?;
+        external.to_json.as_ref().map(|to_json| to_json.as_str())
+    }
+
+    fn external_from_json(&self, type_name: &str) -> Option<&str> {
+        let external = self.library.external_type(type_name)?;
+        external
+            .from_json
+            .as_ref()
+            .map(|from_json| from_json.as_str())
+    }
 }
@@ -6,15 +6,26 @@ use knus::{
 };
 use miette::{IntoDiagnostic, Result};
 
-use crate::{codegen::util::braced, context::Context, model::*};
+use crate::{
+    codegen::util::{
+        braced, class_header, split_library_directive, write_rebuild, write_record_literal,
+        write_update,
+    },
+    context::{Context, Ty, TyKind},
+    json::JsonDocument,
+    model::*,
+};
 
-pub use util::dart_format;
+pub use util::{dart_format, dart_string_literal, escape_dart_string};
 
+mod constraints;
 mod enumeration;
 mod equals;
 mod immutable;
 mod json;
 mod mutable;
+mod ordering;
+mod test_suite;
 mod union;
 mod util;
 
@@ -31,7 +42,19 @@ impl Context {
 
         writeln!(buf, "// ignore_for_file: unnecessary_cast").into_diagnostic()?;
 
-        if let Some(preamble) = &self.library.preamble {
+        // a `library` directive has to come before any imports
+        let preamble = self.library.preamble.as_deref().unwrap_or_default();
+        let (library_directive, preamble) = split_library_directive(preamble);
+        write!(buf, "{library_directive}").into_diagnostic()?;
+
+        // parts share the imports of their library, which validation warns about
+        if !self.library.is_part_file() {
+            for import in self.library.external_imports() {
+                writeln!(buf, "import {};", dart_string_literal(import)).into_diagnostic()?;
+            }
+        }
+
+        if self.library.preamble.is_some() {
             writeln!(buf, "{preamble}").into_diagnostic()?;
         }
 
@@ -43,7 +66,7 @@ impl Context {
         }
 
         for union in &self.library.unions {
-            self.codegen_union_class(&mut buf, union)
+            self.codegen_union_class(&mut buf, union, None)
                 .into_diagnostic()?;
         }
 
@@ -87,6 +110,164 @@ impl Context {
 
         Ok(())
     }
+
+    /// Writes the doc comment of a class, union or enum, followed by its `example` documents
+    fn write_type_docs(
+        &self,
+        buf: &mut String,
+        docs: Option<&SpannedScalar<String>>,
+        examples: &[SpannedScalar<String>],
+    ) -> std::fmt::Result {
+        if let Some(docs) = docs {
+            self.write_doc_comment(buf, docs)?;
+        }
+
+        for (index, example) in examples.iter().enumerate() {
+            if docs.is_some() || index > 0 {
+                writeln!(buf, "///")?;
+            }
+
+            // Examples are checked during validation, so the raw text is only a fallback
+            let json = match JsonDocument::parse("example", example.to_string()) {
+                Ok(document) => document.root.to_json().to_string(),
+                Err(_) => example.trim().to_string(),
+            };
+
+            writeln!(buf, "/// Example JSON:")?;
+            writeln!(buf, "/// ```json")?;
+            for line in json.lines() {
+                writeln!(buf, "/// {line}")?;
+            }
+            writeln!(buf, "/// ```")?;
+        }
+
+        Ok(())
+    }
+
+    /// Formats the `defaults-to` value of a field as a constant expression
+    fn format_default(&self, field: &Field, defaults_to: &DefaultValue) -> String {
+        let ty = self.parse_ty(&field.ty).0.unwrap();
+        self.format_default_value(&ty, defaults_to, false)
+    }
+
+    /// Formats a default of type `ty`. Strings are treated as variant names for enums. `nested`
+    /// values are already inside a `const` expression, so they don't need their own `const`
+    fn format_default_value(&self, ty: &Ty, default: &DefaultValue, nested: bool) -> String {
+        if let Some(value) = default.value() {
+            return self.format_default_scalar(ty, value);
+        }
+
+        let ty = match &ty.kind {
+            TyKind::Nullable(inner) => inner,
+            _ => ty,
+        };
+        let const_kw = if nested { "" } else { "const " };
+
+        match &ty.kind {
+            TyKind::List(inner) | TyKind::Set(inner) => {
+                let items: Vec<_> = default
+                    .items
+                    .iter()
+                    .map(|item| self.format_default_value(inner, item, true))
+                    .collect();
+                let items = items.join(", ");
+
+                match &ty.kind {
+                    TyKind::List(_) => format!("{const_kw}<{inner}>[{items}]"),
+                    _ => format!("{const_kw}<{inner}>{{{items}}}"),
+                }
+            }
+            TyKind::Map { key, value } => {
+                let entries: Vec<_> = default
+                    .entries
+                    .iter()
+                    .map(|entry| {
+                        let key = self.format_default_scalar(key, &entry.key);
+                        let value =
+                            self.format_default_value(value, &entry.to_default_value(), true);
+                        format!("{key}: {value}")
+                    })
+                    .collect();
+
+                format!("{const_kw}<{key}, {value}>{{{}}}", entries.join(", "))
+            }
+            TyKind::Simple(name) => {
+                let class = self
+                    .library
+                    .all_classes()
+                    .find(|class| class.name.as_str() == name)
+                    .expect("checked in validation");
+
+                let fields: Vec<_> = default
+                    .fields
+                    .iter()
+                    .map(|assignment| {
+                        let Literal::String(field_name) = &*assignment.key.literal else {
+                            unreachable!("checked in validation");
+                        };
+                        let field = class
+                            .fields
+                            .iter()
+                            .find(|field| field.name.as_str() == &**field_name)
+                            .expect("checked in validation");
+                        let ty = self.parse_ty(&field.ty).0.unwrap();
+                        let value =
+                            self.format_default_value(&ty, &assignment.to_default_value(), true);
+
+                        format!("{field_name}: {value}")
+                    })
+                    .collect();
+
+                format!("{const_kw}{name}({})", fields.join(", "))
+            }
+            TyKind::Nullable(_) => unreachable!("nested nullable types are not allowed"),
+            TyKind::Record { .. } | TyKind::Generic { .. } => {
+                unreachable!("checked in validation")
+            }
+        }
+    }
+
+    fn format_default_scalar(&self, ty: &Ty, value: &Value<Span>) -> String {
+        if let Literal::String(variant) = &*value.literal
+            && let Some(enumeration) = self.ty_enum(ty)
+        {
+            return format!("{}.{variant}", enumeration.name);
+        }
+
+        format_dart_literal_const(value)
+    }
+
+    fn field_is_nullable(&self, field: &Field) -> bool {
+        self.parse_ty(&field.ty)
+            .0
+            .is_some_and(|ty| matches!(ty.kind, TyKind::Nullable(_)))
+    }
+
+    /// Whether the builder of `class` leaves `field` unset until it is assigned, which is the
+    /// case for fields without a default or a nullable type in partial builders
+    fn is_partial_builder_field(&self, class: &Class, field: &Field) -> bool {
+        self.library.builder_mode_for(class) == BuilderMode::Partial
+            && field.defaults_to.is_none()
+            && field.defaults_to_dart.is_none()
+            && !self.field_is_nullable(field)
+    }
+
+    /// The enum that `ty` refers to, if it is an enum (or a nullable enum)
+    pub(crate) fn ty_enum(&self, ty: &Ty) -> Option<&Enum> {
+        let ty = match &ty.kind {
+            TyKind::Nullable(inner) => inner,
+            _ => ty,
+        };
+
+        let TyKind::Simple(name) = &ty.kind else {
+            return None;
+        };
+
+        self.library
+            .enums
+            .iter()
+            .find(|enumeration| enumeration.name.as_str() == name)
+    }
 }
 
 pub fn format_dart_literal_const(defaults_to: &Value<Span>) -> String {
@@ -104,7 +285,6 @@ pub fn format_dart_literal_const(defaults_to: &Value<Span>) -> String {
             format!("{prefix}{str}")
         }
         Literal::Decimal(Decimal(str)) => str.to_string(),
-        // TODO: deal with escaping
-        Literal::String(str) => format!("\"{str}\""),
+        Literal::String(str) => dart_string_literal(str),
     }
 }
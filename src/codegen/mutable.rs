@@ -28,44 +28,118 @@ impl Context {
             writeln!(buf, "{annotations}")?;
         }
 
-        write!(buf, "final class {builder_name} ",)?;
-        if let Some(superclass) = &superclass {
-            write!(buf, "extends {}Builder ", superclass.name)?;
-        }
+        let modifier = self
+            .library
+            .class_builder_modifier(class)
+            .map_or(ClassModifier::Final, |modifier| modifier.value);
+        let extends = superclass.map(|superclass| format!("{}Builder", superclass.name));
+        class_header(
+            buf,
+            modifier.prefix(),
+            &builder_name,
+            extends.as_deref(),
+            &class.builder_with,
+            &class.builder_implements,
+        )?;
+
+        let partial = self.library.builder_mode_for(class) == BuilderMode::Partial;
 
         braced(buf, |out| {
             for field in &class.fields {
                 self.write_builder_ty(out, &self.parse_ty(&field.ty).0.unwrap())?;
+                if self.is_partial_builder_field(class, field) {
+                    write!(out, "?")?;
+                }
                 writeln!(out, " {};", field.name)?;
             }
 
             writeln!(out)?;
 
+            let mut initializers = vec![];
+
             if class.fields.is_empty() {
                 writeln!(out, "{builder_name}()")?;
             } else {
                 writeln!(out, "{builder_name}({{")?;
                 for field in &class.fields {
-                    writeln!(out, "required this.{},", field.name)?;
+                    let name = &field.name;
+
+                    // partial builders start out with the defaults, so nothing is required
+                    if partial && let Some(default) = self.field_default(field) {
+                        let ty = self.parse_ty(&field.ty).0.unwrap();
+                        let mut builder_ty = String::new();
+                        self.write_builder_ty(&mut builder_ty, &ty)?;
+
+                        let inner = match &ty.kind {
+                            TyKind::Nullable(inner) => inner,
+                            _ => &ty,
+                        };
+                        let collection = matches!(
+                            inner.kind,
+                            TyKind::List(_) | TyKind::Set(_) | TyKind::Map { .. }
+                        );
+
+                        // builders and collections need a fresh (modifiable) copy of the
+                        // default, which can't be a constant
+                        if builder_ty == ty.to_string() && !collection {
+                            writeln!(out, "this.{name} = {default},")?;
+                        } else {
+                            let nullable = match &ty.kind {
+                                TyKind::Nullable(_) => "",
+                                _ => "?",
+                            };
+                            writeln!(out, "{builder_ty}{nullable} {name},")?;
+
+                            let mut initializer = format!("{name} = {name} ?? ");
+                            self.write_to_builder_expr(
+                                &mut initializer,
+                                &format!("({default})"),
+                                inner,
+                            )?;
+                            initializers.push(initializer);
+                        }
+                        continue;
+                    }
+
+                    // otherwise, partial builders only require fields that have a default,
+                    // since those aren't nullable in the builder
+                    let nullable = self.is_partial_builder_field(class, field)
+                        || self.field_is_nullable(field);
+                    match partial && nullable {
+                        true => writeln!(out, "this.{name},")?,
+                        false => writeln!(out, "required this.{name},")?,
+                    }
                 }
                 write!(out, "}})")?;
             }
 
-            match superclass {
-                Some(_) => writeln!(out, " : super();")?,
-                None => writeln!(out, ";")?,
+            if superclass.is_some() {
+                initializers.push("super()".to_string());
+            }
+
+            match initializers.is_empty() {
+                true => writeln!(out, ";")?,
+                false => writeln!(out, " : {};", initializers.join(", "))?,
             }
 
             writeln!(out)?;
 
-            writeln!(out, "{0} build() => {0}(", class.name)?;
-            for field in &class.fields {
-                let name = &field.name;
-                write!(out, "{name}: ")?;
-                self.write_build_expr(out, name, &self.parse_ty(&field.ty).0.unwrap())?;
-                writeln!(out, ",")?;
+            match partial {
+                true => self.generate_partial_build(out, class)?,
+                false => {
+                    writeln!(out, "{0} build() => {0}(", class.name)?;
+                    self.write_build_args(out, class)?;
+                    writeln!(out, ");")?;
+                }
+            }
+
+            writeln!(out)?;
+            write_update(out, &class.name, superclass.is_some())?;
+
+            if self.has_constraints(class) {
+                writeln!(out)?;
+                self.generate_validate(out, class, true)?;
             }
-            writeln!(out, ");")?;
 
             if let Some(extra) = &class.builder_extra_dart {
                 writeln!(out, "{extra}")?;
@@ -78,6 +152,74 @@ impl Context {
         Ok(())
     }
 
+    /// The default value of `field` as a constant expression, unless it has no default (or the
+    /// default is `null`)
+    fn field_default(&self, field: &Field) -> Option<String> {
+        let default = match (&field.defaults_to, &field.defaults_to_dart) {
+            (Some(defaults_to), _) => self.format_default(field, defaults_to),
+            (None, Some(defaults_to_dart)) => defaults_to_dart.to_string(),
+            (None, None) => return None,
+        };
+
+        (default != "null").then_some(default)
+    }
+
+    /// Writes the named arguments that pass every field of the builder to the constructor of
+    /// `class`. Fields that are unset in a partial builder are assumed to be set by now
+    fn write_build_args(&self, buf: &mut String, class: &Class) -> std::fmt::Result {
+        for field in &class.fields {
+            let name = &field.name;
+            let ty = self.parse_ty(&field.ty).0.unwrap();
+            write!(buf, "{name}: ")?;
+
+            match self.is_partial_builder_field(class, field) {
+                true => {
+                    let mut builder_ty = String::new();
+                    self.write_builder_ty(&mut builder_ty, &ty)?;
+                    self.write_build_expr(buf, &format!("({name} as {builder_ty})"), &ty)?;
+                }
+                false => self.write_build_expr(buf, name, &ty)?,
+            }
+            writeln!(buf, ",")?;
+        }
+
+        Ok(())
+    }
+
+    /// Generates `build`, `isComplete` and `missingFields` for a partial builder, where `build`
+    /// throws a [StateError] that lists every field that hasn't been set
+    fn generate_partial_build(&self, buf: &mut String, class: &Class) -> std::fmt::Result {
+        let class_name = &class.name;
+        let missing = dart_string_literal(&format!("Can't build {class_name}, missing: "));
+
+        writeln!(buf, "{class_name} build()")?;
+        braced(buf, |out| {
+            writeln!(out, "final missing = missingFields;")?;
+            writeln!(out, "if (missing.isNotEmpty)")?;
+            braced(out, |out| {
+                writeln!(out, "throw StateError({missing} + missing.join(\", \"));")
+            })?;
+            writeln!(out)?;
+            writeln!(out, "return {class_name}(")?;
+            self.write_build_args(out, class)?;
+            writeln!(out, ");")
+        })?;
+        writeln!(buf)?;
+
+        writeln!(buf, "bool get isComplete => missingFields.isEmpty;")?;
+        writeln!(buf)?;
+
+        writeln!(buf, "List<String> get missingFields => [")?;
+        for field in &class.fields {
+            if self.is_partial_builder_field(class, field) {
+                let name = &field.name;
+                let label = dart_string_literal(name);
+                writeln!(buf, "if ({name} == null) {label},")?;
+            }
+        }
+        writeln!(buf, "];")
+    }
+
     pub(super) fn write_builder_ty(&self, buf: &mut String, ty: &Ty) -> std::fmt::Result {
         match &ty.kind {
             TyKind::Simple(ident) if self.library.type_has_builder(ident) => {
@@ -86,6 +228,11 @@ impl Context {
             TyKind::Simple(ident) => {
                 write!(buf, "{ident}")?;
             }
+            // generic types are passed through as they are, since their type arguments can't be
+            // converted to builders
+            TyKind::Generic { .. } => {
+                write!(buf, "{ty}")?;
+            }
             TyKind::List(inner) => {
                 write!(buf, "List<")?;
                 self.write_builder_ty(buf, inner)?;
@@ -96,8 +243,8 @@ impl Context {
                 self.write_builder_ty(buf, inner)?;
                 write!(buf, ">")?;
             }
-            TyKind::Map { value, .. } => {
-                write!(buf, "Map<String, ")?;
+            TyKind::Map { key, value } => {
+                write!(buf, "Map<{key}, ")?;
                 self.write_builder_ty(buf, value)?;
                 write!(buf, ">")?;
             }
@@ -105,6 +252,29 @@ impl Context {
                 self.write_builder_ty(buf, inner)?;
                 write!(buf, "?")?;
             }
+            TyKind::Record { positional, named } => {
+                let mut fields = vec![];
+                for ty in positional {
+                    let mut field = String::new();
+                    self.write_builder_ty(&mut field, ty)?;
+                    fields.push(field);
+                }
+
+                if !named.is_empty() {
+                    let mut named_fields = vec![];
+                    for (name, ty) in named {
+                        let mut field = String::new();
+                        self.write_builder_ty(&mut field, ty)?;
+                        named_fields.push(format!("{field} {name}"));
+                    }
+                    fields.push(format!("{{{}}}", named_fields.join(", ")));
+                }
+
+                match (positional.len(), named.len()) {
+                    (1, 0) => write!(buf, "({},)", fields[0])?,
+                    _ => write!(buf, "({})", fields.join(", "))?,
+                }
+            }
         }
 
         Ok(())
@@ -115,7 +285,7 @@ impl Context {
             TyKind::Simple(ident) if self.library.type_has_builder(ident) => {
                 write!(buf, "{expr}.build()")?;
             }
-            TyKind::Simple(_) => {
+            TyKind::Simple(_) | TyKind::Generic { .. } => {
                 write!(buf, "{expr}")?;
             }
             TyKind::List(inner) => {
@@ -133,6 +303,11 @@ impl Context {
                 self.write_build_expr(buf, "value", value)?;
                 write!(buf, "))")?;
             }
+            TyKind::Record { positional, named } => {
+                write_record_literal(buf, positional, named, |buf, _, getter, ty| {
+                    self.write_build_expr(buf, &format!("{expr}.{getter}"), ty)
+                })?;
+            }
             TyKind::Nullable(inner) => {
                 write!(buf, "{expr} == null ? null : ")?;
                 let mut inner_builder_ty = String::new();
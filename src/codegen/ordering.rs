@@ -0,0 +1,101 @@
+use crate::context::{Ty, TyKind};
+
+use super::*;
+
+impl Context {
+    /// Generates `compareTo` for a class with an `ordering`, comparing each field in turn until
+    /// one differs
+    pub(super) fn generate_compare_to(
+        &self,
+        buf: &mut String,
+        class: &Class,
+        ordering: &Ordering,
+    ) -> std::fmt::Result {
+        let class_name = &class.name;
+
+        writeln!(buf, "@override\nint compareTo({class_name} other)")?;
+        braced(buf, |out| {
+            writeln!(out, "int result;")?;
+
+            for key in &ordering.fields {
+                // checked during validation
+                let field = class
+                    .fields
+                    .iter()
+                    .find(|field| field.name.value == key.name.value)
+                    .unwrap();
+                let ty = self.parse_ty(&field.ty).0.unwrap();
+                let name = &field.name;
+                let other = format!("other.{name}");
+
+                let descending =
+                    key.order.as_ref().map(|order| order.value) == Some(SortOrder::Desc);
+                let (left, right) = match descending {
+                    true => (other.as_str(), name.as_str()),
+                    false => (name.as_str(), other.as_str()),
+                };
+
+                let TyKind::Nullable(inner) = &ty.kind else {
+                    write!(out, "result = ")?;
+                    self.write_compare(out, left, right, &ty)?;
+                    writeln!(out, ";")?;
+                    writeln!(out, "if (result != 0) {{ return result; }}")?;
+                    continue;
+                };
+
+                // nulls go first or last regardless of `order`
+                let null_first = match key.order_nulls.as_ref().map(|nulls| nulls.value) {
+                    Some(NullsOrder::Last) => 1,
+                    _ => -1,
+                };
+                writeln!(out, "if ({name} == null || {other} == null)")?;
+                braced(out, |out| {
+                    writeln!(
+                        out,
+                        "if ({name} != {other}) {{ return {name} == null ? {null_first} : {}; }}",
+                        -null_first
+                    )
+                })?;
+                writeln!(out, "else")?;
+                braced(out, |out| {
+                    write!(out, "result = ")?;
+                    self.write_compare(
+                        out,
+                        &format!("({left} as {inner})"),
+                        &format!("({right} as {inner})"),
+                        inner,
+                    )?;
+                    writeln!(out, ";")?;
+                    writeln!(out, "if (result != 0) {{ return result; }}")
+                })?;
+            }
+
+            writeln!(out, "return 0;")
+        })
+    }
+
+    /// Writes an expression that compares two non-null values of type `ty`. Enums are compared by
+    /// declaration order, and `bool`, which isn't `Comparable`, puts `false` first
+    fn write_compare(
+        &self,
+        buf: &mut String,
+        left: &str,
+        right: &str,
+        ty: &Ty,
+    ) -> std::fmt::Result {
+        let ident = match &ty.kind {
+            TyKind::Simple(ident) | TyKind::Generic { name: ident, .. } => ident.as_str(),
+            _ => unreachable!("checked during validation"),
+        };
+
+        if ident == "bool" {
+            return write!(buf, "({left} == {right} ? 0 : ({left} ? 1 : -1))");
+        }
+
+        let is_enum = self.library.enums.iter().any(|e| e.name.as_str() == ident);
+        match is_enum {
+            true => write!(buf, "{left}.index.compareTo({right}.index)"),
+            false => write!(buf, "{left}.compareTo({right})"),
+        }
+    }
+}
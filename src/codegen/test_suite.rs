@@ -0,0 +1,502 @@
+use crate::context::{Ty, TyKind, record_fields};
+
+use super::*;
+
+/// How deep values are nested before only the smallest possible values are used
+const MAX_DEPTH: usize = 3;
+
+/// Nesting deeper than this can only come from types which contain themselves without a
+/// nullable type or collection in between, which can't be constructed at all
+const DEPTH_LIMIT: usize = 12;
+
+/// The most items put into a sampled collection
+const MAX_ITEMS: usize = 3;
+
+/// The longest string generated for a `max-length` constraint
+const MAX_SAMPLE_LENGTH: u64 = 64;
+
+/// A Dart expression for a value of some type, used to construct instances in generated tests
+#[derive(Debug, Clone)]
+struct Sample {
+    dart: String,
+    /// The value of a number, used to check `min` and `max`
+    number: Option<f64>,
+    /// The length of a string or collection, used to check length and item constraints
+    len: Option<usize>,
+}
+
+impl Sample {
+    fn new(dart: impl Into<String>) -> Self {
+        Sample {
+            dart: dart.into(),
+            number: None,
+            len: None,
+        }
+    }
+
+    fn number(dart: impl Into<String>, number: f64) -> Self {
+        Sample {
+            number: Some(number),
+            ..Sample::new(dart)
+        }
+    }
+
+    fn string(value: &str) -> Self {
+        Sample {
+            len: Some(value.chars().count()),
+            ..Sample::new(dart_string_literal(value))
+        }
+    }
+
+    fn collection(dart: String, len: usize) -> Self {
+        Sample {
+            len: Some(len),
+            ..Sample::new(dart)
+        }
+    }
+}
+
+impl Context {
+    #[cfg(test)]
+    pub fn codegen_tests_to_string(&self, import: &str) -> Result<String> {
+        let mut buf = Vec::new();
+        self.codegen_tests(&mut buf, import)?;
+        Ok(String::from_utf8(buf).unwrap())
+    }
+
+    /// Generates a `package:test` suite which checks that every generated type survives a round
+    /// trip through JSON and through its builder, and that `==` and `hashCode` agree. `import` is
+    /// how the suite imports the generated library
+    pub fn codegen_tests(&self, out: &mut impl std::io::Write, import: &str) -> Result<()> {
+        let mut buf = String::new();
+
+        self.write_test_suite(&mut buf, import).into_diagnostic()?;
+
+        let lang_version = self
+            .library
+            .defaults
+            .as_ref()
+            .and_then(|d| d.dart_format_language_version.as_ref())
+            .map(|v| v.as_str());
+
+        let formatted = dart_format(buf, lang_version)?;
+        out.write_all(formatted.as_bytes()).into_diagnostic()?;
+
+        Ok(())
+    }
+
+    fn write_test_suite(&self, buf: &mut String, import: &str) -> std::fmt::Result {
+        writeln!(buf, "// Generated by dart-typegen. Do not edit.")?;
+        writeln!(buf, "import 'dart:convert';")?;
+        writeln!(buf)?;
+        writeln!(buf, "import 'package:test/test.dart';")?;
+        writeln!(buf)?;
+        writeln!(buf, "import {};", dart_string_literal(import))?;
+        writeln!(buf)?;
+
+        writeln!(buf, "void main()")?;
+        braced(buf, |out| {
+            for class in &self.library.classes {
+                self.write_class_tests(out, class, None)?;
+            }
+
+            for union in self.library.all_unions() {
+                for class in &union.classes {
+                    self.write_class_tests(out, class, Some(union))?;
+                }
+            }
+
+            for enumeration in &self.library.enums {
+                self.write_enum_tests(out, enumeration)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn write_class_tests(
+        &self,
+        buf: &mut String,
+        class: &Class,
+        union: Option<&Union>,
+    ) -> std::fmt::Result {
+        let name = &class.name;
+
+        let Some(samples) = self.class_samples(class, 0) else {
+            writeln!(
+                buf,
+                "// {name} is not tested, since values can't be generated for all of its fields"
+            )?;
+            return Ok(());
+        };
+
+        // without a generated `==`, values are compared by their JSON instead
+        let generates_equals = self
+            .library
+            .defaults
+            .as_ref()
+            .and_then(|d| d.generate_equals.as_ref())
+            .map(|g| g.value)
+            .unwrap_or(true);
+        let expect_same = |actual: &str| match generates_equals {
+            true => format!("expect({actual}, equals(value));"),
+            false => format!("expect(jsonEncode({actual}.toJson()), jsonEncode(value.toJson()));"),
+        };
+
+        writeln!(buf, "group({}, ()", dart_string_literal(name))?;
+        braced(buf, |out| {
+            writeln!(out, "final values = <{name}>[")?;
+            for sample in &samples {
+                writeln!(out, "{},", sample.dart)?;
+            }
+            writeln!(out, "];")?;
+            writeln!(out)?;
+
+            writeln!(out, "for (final (i, value) in values.indexed)")?;
+            braced(out, |out| {
+                writeln!(out, "test('round-trips through JSON (#$i)', ()")?;
+                braced(out, |out| {
+                    writeln!(
+                        out,
+                        "final json = jsonDecode(jsonEncode(value.toJson())) as Map<String, dynamic>;"
+                    )?;
+                    writeln!(out, "{}", expect_same(&format!("{name}.fromJson(json)")))?;
+
+                    // union members must also be decoded by each union that contains them
+                    if let Some(union) = union {
+                        for union in self.library.union_ancestors(union) {
+                            let union_name = &union.name;
+                            writeln!(
+                                out,
+                                "{}",
+                                expect_same(&format!("{union_name}.fromJson(json)"))
+                            )?;
+                        }
+                    }
+
+                    Ok(())
+                })?;
+                writeln!(out, ");")?;
+
+                writeln!(out, "test('round-trips through its builder (#$i)', ()")?;
+                braced(out, |out| {
+                    writeln!(out, "{}", expect_same("value.toBuilder().build()"))
+                })?;
+                writeln!(out, ");")?;
+
+                if generates_equals {
+                    writeln!(
+                        out,
+                        "test('is equal to a copy, with the same hashCode (#$i)', ()"
+                    )?;
+                    braced(out, |out| {
+                        writeln!(out, "final copy = value.toBuilder().build();")?;
+                        writeln!(out, "expect(value == value, isTrue);")?;
+                        writeln!(out, "expect(value == copy, isTrue);")?;
+                        writeln!(out, "expect(copy == value, isTrue);")?;
+                        writeln!(out, "expect(copy.hashCode, value.hashCode);")
+                    })?;
+                    writeln!(out, ");")?;
+                }
+
+                Ok(())
+            })
+        })?;
+        writeln!(buf, ");")?;
+
+        Ok(())
+    }
+
+    fn write_enum_tests(&self, buf: &mut String, enumeration: &Enum) -> std::fmt::Result {
+        let name = &enumeration.name;
+
+        writeln!(buf, "group({}, ()", dart_string_literal(name))?;
+        braced(buf, |out| {
+            writeln!(out, "for (final value in {name}.values)")?;
+            braced(out, |out| {
+                writeln!(out, "test('round-trips through JSON ($value)', ()")?;
+                braced(out, |out| {
+                    writeln!(out, "final json = jsonDecode(jsonEncode(value.toJson()));")?;
+                    writeln!(out, "expect({name}.fromJson(json), value);")
+                })?;
+                writeln!(out, ");")
+            })
+        })?;
+        writeln!(buf, ");")?;
+
+        Ok(())
+    }
+
+    /// Instances of `class`. Every field gets its smallest value (nulls, empty collections) in
+    /// the first instance, and each of its other values in at least one instance, so that every
+    /// enum variant and union member is covered. Returns `None` if a field has no values which
+    /// can be generated, e.g. because it is an external type
+    fn class_samples(&self, class: &Class, depth: usize) -> Option<Vec<Sample>> {
+        let fields = class
+            .fields
+            .iter()
+            .map(|field| Some((field, self.field_samples(field, depth)?)))
+            .collect::<Option<Vec<_>>>()?;
+
+        // nested instances only need a few values, otherwise the tests grow very quickly
+        let mut count = fields.iter().map(|(_, samples)| samples.len()).max();
+        if depth > 0 {
+            count = count.map(|count| count.min(2));
+        }
+
+        let samples = (0..count.unwrap_or(1))
+            .map(|i| {
+                let args: Vec<_> = fields
+                    .iter()
+                    .map(|(field, samples)| {
+                        format!("{}: {}", field.name, samples[i % samples.len()].dart)
+                    })
+                    .collect();
+
+                Sample::new(format!("{}({})", class.name, args.join(", ")))
+            })
+            .collect();
+
+        Some(samples)
+    }
+
+    /// Values of the field's type which satisfy its constraints
+    fn field_samples(&self, field: &Field, depth: usize) -> Option<Vec<Sample>> {
+        let ty = self.parse_ty(&field.ty).0?;
+        let mut samples = self.samples(&ty, depth).unwrap_or_default();
+
+        let inner = match &ty.kind {
+            TyKind::Nullable(inner) => inner,
+            _ => &ty,
+        };
+
+        // values at the limits of the constraints
+        for bound in [&field.min, &field.max].into_iter().flatten() {
+            if let Some(number) = literal_number(bound) {
+                samples.push(Sample::number(format_dart_literal_const(bound), number));
+            }
+        }
+        for length in [&field.min_length, &field.max_length].into_iter().flatten() {
+            if length.value <= MAX_SAMPLE_LENGTH {
+                samples.push(Sample::string(&"a".repeat(length.value as usize)));
+            }
+        }
+        for items in [&field.min_items, &field.max_items].into_iter().flatten() {
+            samples.extend(self.collection_sample(inner, items.value as usize, depth));
+        }
+
+        let within = |value: Option<f64>, min: Option<f64>, max: Option<f64>| match value {
+            Some(value) => min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max),
+            None => true,
+        };
+        let len_within = |len: Option<usize>,
+                          min: Option<&SpannedScalar<u64>>,
+                          max: Option<&SpannedScalar<u64>>| {
+            within(
+                len.map(|len| len as f64),
+                min.map(|min| min.value as f64),
+                max.map(|max| max.value as f64),
+            )
+        };
+
+        let min = field.min.as_ref().and_then(literal_number);
+        let max = field.max.as_ref().and_then(literal_number);
+
+        samples.retain(|sample| {
+            // strings matching a pattern can't be generated, so only null is left
+            let matches_pattern = field.pattern.is_none() || sample.dart == "null";
+
+            matches_pattern
+                && within(sample.number, min, max)
+                && len_within(
+                    sample.len,
+                    field.min_length.as_ref(),
+                    field.max_length.as_ref(),
+                )
+                && len_within(
+                    sample.len,
+                    field.min_items.as_ref(),
+                    field.max_items.as_ref(),
+                )
+        });
+
+        let mut seen = std::collections::HashSet::new();
+        samples.retain(|sample| seen.insert(sample.dart.clone()));
+
+        (!samples.is_empty()).then_some(samples)
+    }
+
+    /// Values of `ty`, starting with the smallest. Past [`MAX_DEPTH`], only the smallest value
+    /// is generated
+    fn samples(&self, ty: &Ty, depth: usize) -> Option<Vec<Sample>> {
+        if depth > DEPTH_LIMIT {
+            return None;
+        }
+
+        let minimal = depth >= MAX_DEPTH;
+
+        let samples = match &ty.kind {
+            TyKind::Nullable(_) if minimal => vec![Sample::new("null")],
+            TyKind::Nullable(inner) => {
+                let mut samples = vec![Sample::new("null")];
+                samples.extend(self.samples(inner, depth).unwrap_or_default());
+                samples
+            }
+            TyKind::List(_) | TyKind::Set(_) | TyKind::Map { .. } => {
+                let mut samples = vec![self.collection_sample(ty, 0, depth)?];
+                if !minimal {
+                    // sets and maps may have fewer distinct values to choose from
+                    samples.extend(
+                        (1..=MAX_ITEMS)
+                            .rev()
+                            .find_map(|len| self.collection_sample(ty, len, depth)),
+                    );
+                }
+                samples
+            }
+            TyKind::Record { positional, named } => {
+                let fields = record_fields(positional, named)
+                    .into_iter()
+                    .map(|(_, ty)| self.samples(ty, depth + 1))
+                    .collect::<Option<Vec<_>>>()?;
+                let count = match minimal {
+                    true => 1,
+                    false => fields
+                        .iter()
+                        .map(|samples| samples.len())
+                        .max()
+                        .unwrap_or(1),
+                };
+
+                (0..count)
+                    .map(|i| {
+                        let mut buf = String::new();
+                        write_record_literal(&mut buf, positional, named, |buf, j, _, _| {
+                            let samples = &fields[j];
+                            write!(buf, "{}", samples[i % samples.len()].dart)
+                        })
+                        .unwrap();
+                        Sample::new(buf)
+                    })
+                    .collect()
+            }
+            TyKind::Simple(name) => self.named_samples(name, depth)?,
+            TyKind::Generic { .. } => return None,
+        };
+
+        match minimal {
+            true => Some(samples.into_iter().take(1).collect()),
+            false => Some(samples),
+        }
+    }
+
+    /// Values of a built-in type, or of an enum, class or union from the library
+    fn named_samples(&self, name: &str, depth: usize) -> Option<Vec<Sample>> {
+        let samples = match name {
+            "int" => vec![
+                Sample::number("0", 0.0),
+                Sample::number("-7", -7.0),
+                Sample::number("42", 42.0),
+            ],
+            "double" | "num" => vec![
+                Sample::number("0.0", 0.0),
+                Sample::number("-1.5", -1.5),
+                Sample::number("3.25", 3.25),
+            ],
+            "String" => vec![
+                Sample::string(""),
+                Sample::string("hello"),
+                Sample::string("with \"quotes\", $dollars, \\backslashes\\ and\nnewlines"),
+            ],
+            "bool" => vec![Sample::new("false"), Sample::new("true")],
+            _ => {
+                if let Some(enumeration) =
+                    self.library.enums.iter().find(|e| e.name.as_str() == name)
+                {
+                    return Some(
+                        enumeration
+                            .variants
+                            .iter()
+                            .map(|variant| Sample::new(format!("{name}.{}", variant.name)))
+                            .collect(),
+                    );
+                }
+
+                if let Some(class) = self.library.all_classes().find(|c| c.name.as_str() == name) {
+                    return self.class_samples(class, depth + 1);
+                }
+
+                let union = self
+                    .library
+                    .all_unions()
+                    .find(|u| u.name.as_str() == name)?;
+
+                // one instance of every member, including the members of nested unions
+                let samples: Vec<_> = self
+                    .library
+                    .union_members(union)
+                    .into_iter()
+                    .filter_map(|class| self.class_samples(class, depth + 1)?.into_iter().next())
+                    .collect();
+
+                return (!samples.is_empty()).then_some(samples);
+            }
+        };
+
+        Some(samples)
+    }
+
+    /// A collection of type `ty` with `len` items, or `None` if there aren't enough different
+    /// values of the item type to fill a set or the keys of a map
+    fn collection_sample(&self, ty: &Ty, len: usize, depth: usize) -> Option<Sample> {
+        let items = |ty: &Ty, distinct: bool| -> Option<Vec<String>> {
+            if len == 0 {
+                return Some(vec![]);
+            }
+
+            let samples = self.samples(ty, depth + 1)?;
+            if distinct && samples.len() < len {
+                return None;
+            }
+
+            Some(
+                samples
+                    .iter()
+                    .cycle()
+                    .take(len)
+                    .map(|sample| sample.dart.clone())
+                    .collect(),
+            )
+        };
+
+        let dart = match &ty.kind {
+            TyKind::List(inner) => format!("<{inner}>[{}]", items(inner, false)?.join(", ")),
+            TyKind::Set(inner) => format!("<{inner}>{{{}}}", items(inner, true)?.join(", ")),
+            TyKind::Map { key, value } => {
+                let keys = items(key, true)?;
+                let values = items(value, false)?;
+                let entries: Vec<_> = keys
+                    .iter()
+                    .zip(&values)
+                    .map(|(key, value)| format!("{key}: {value}"))
+                    .collect();
+
+                format!("<{key}, {value}>{{{}}}", entries.join(", "))
+            }
+            _ => return None,
+        };
+
+        Some(Sample::collection(dart, len))
+    }
+}
+
+/// The value of a numeric KDL literal
+fn literal_number(value: &Value<Span>) -> Option<f64> {
+    match &*value.literal {
+        Literal::Int(Integer(Radix::Dec, str)) | Literal::Decimal(Decimal(str)) => str.parse().ok(),
+        Literal::Int(Integer(Radix::Hex, str)) => {
+            i64::from_str_radix(str, 16).ok().map(|n| n as f64)
+        }
+        _ => None,
+    }
+}
@@ -1,10 +1,15 @@
 use super::*;
 
 impl Context {
-    pub(super) fn codegen_union_class(&self, buf: &mut String, union: &Union) -> std::fmt::Result {
-        if let Some(docs) = &union.docs {
-            self.write_doc_comment(buf, docs)?;
-        }
+    /// Generates a union, its builder, and all its members. `parent` is the union that contains
+    /// this union, if it is nested
+    pub(super) fn codegen_union_class(
+        &self,
+        buf: &mut String,
+        union: &Union,
+        parent: Option<&Union>,
+    ) -> std::fmt::Result {
+        self.write_type_docs(buf, union.docs.as_ref(), &union.examples)?;
 
         if let Some(annotations) = &union.annotations {
             writeln!(buf, "{annotations}")?;
@@ -19,52 +24,60 @@ impl Context {
             writeln!(buf, "{annotations}")?;
         }
 
-        let modifiers = match self.library.is_sealed(union) {
-            false => "abstract final",
-            true => "sealed",
-        };
+        let modifier = self.library.union_modifier(union);
+        let modifiers = self.union_modifiers(union, modifier);
 
-        write!(buf, "{modifiers} class {} ", union.name)?;
+        class_header(
+            buf,
+            &modifiers,
+            &union.name,
+            parent.map(|parent| parent.name.as_str()),
+            &union.with,
+            &union.implements,
+        )?;
 
-        self.codegen_body(buf, union)?;
+        self.codegen_body(buf, union, parent)?;
 
         for class in &union.classes {
             self.codegen_immutable_class(buf, class, Some(union))?;
             self.codegen_mutable_class(buf, class, Some(union))?;
         }
 
+        for nested in &union.unions {
+            self.codegen_union_class(buf, nested, Some(union))?;
+        }
+
         Ok(())
     }
 
-    fn codegen_body(&self, buf: &mut String, union: &Union) -> std::fmt::Result {
-        let discriminant_key = self.library.discriminant_key_for(union);
-
+    fn codegen_body(
+        &self,
+        buf: &mut String,
+        union: &Union,
+        parent: Option<&Union>,
+    ) -> std::fmt::Result {
         braced(buf, |out| {
             writeln!(out, "const {}();", union.name)?;
             writeln!(out)?;
 
+            if parent.is_some() {
+                writeln!(out, "@override")?;
+            }
             writeln!(out, "{}Builder toBuilder();", union.name)?;
             writeln!(out)?;
 
-            writeln!(out, "Map<String, dynamic> toJson(); ")?;
-            writeln!(
-                out,
-                r#"factory {}.fromJson(Map<String, dynamic> json) => switch (json["{discriminant_key}"]) {{"#,
-                union.name,
-            )?;
-
-            for class in &union.classes {
-                let name = &class.name;
-                let discriminant_value = self.library.discriminant_value_for(union, class);
+            write_rebuild(out, &union.name, parent.is_some())?;
+            writeln!(out)?;
 
-                writeln!(out, "{discriminant_value} => {name}.fromJson(json),")?;
+            if parent.is_some() {
+                writeln!(out, "@override")?;
             }
-            writeln!(
-                out,
-                r#"final other => throw ArgumentError("unknown discriminant: $other"),"#
-            )?;
+            writeln!(out, "Map<String, dynamic> toJson(); ")?;
+            self.generate_union_from_json(out, union)?;
 
-            writeln!(out, "}};")?;
+            if self.library.generates_when(union) {
+                self.generate_when(out, union)?;
+            }
 
             for extra_dart in &union.extra_dart {
                 writeln!(out, "{extra_dart}")?;
@@ -73,7 +86,6 @@ impl Context {
             Ok(())
         })?;
 
-
         if let Some(annotations) = &union.builder_annotations {
             writeln!(buf, "{annotations}")?;
         }
@@ -87,14 +99,110 @@ impl Context {
             writeln!(buf, "{annotations}")?;
         }
 
-        let modifiers = match self.library.is_sealed(union) {
-            false => "abstract final",
-            true => "sealed",
-        };
+        let modifier = self.library.union_builder_modifier(union);
+        let modifiers = self.union_modifiers(union, modifier);
+
+        let extends = parent.map(|parent| format!("{}Builder", parent.name));
+
+        class_header(
+            buf,
+            &modifiers,
+            &format!("{}Builder", union.name),
+            extends.as_deref(),
+            &union.builder_with,
+            &union.builder_implements,
+        )?;
+        braced(buf, |out| {
+            if parent.is_some() {
+                writeln!(out, "@override")?;
+            }
+            writeln!(out, "{} build();", union.name)?;
+            writeln!(out)?;
+
+            write_update(out, &union.name, parent.is_some())
+        })?;
+
+        Ok(())
+    }
+
+    /// Generates `when`, `maybeWhen` and `mapOrNull`, which take a callback for each direct member
+    /// of the union. Sealed unions are exhaustive, so they don't need a fallback case
+    fn generate_when(&self, buf: &mut String, union: &Union) -> std::fmt::Result {
+        let members = self.library.when_members(union);
+        let sealed = self.library.is_sealed(union);
+
+        writeln!(buf)?;
+        writeln!(buf, "R when<R>({{")?;
+        for (name, param) in &members {
+            writeln!(buf, "required R Function({name} {param}) {param},")?;
+        }
+        write!(buf, "}}) => switch (this)")?;
+        braced(buf, |out| {
+            for (name, param) in &members {
+                writeln!(out, "{name} $instance => {param}($instance),")?;
+            }
+            if !sealed {
+                writeln!(
+                    out,
+                    r#"_ => throw StateError("unknown subclass of {}: $runtimeType"),"#,
+                    escape_dart_string(&union.name)
+                )?;
+            }
+            Ok(())
+        })?;
+        writeln!(buf, ";")?;
+        writeln!(buf)?;
+
+        writeln!(buf, "R maybeWhen<R>({{")?;
+        for (name, param) in &members {
+            writeln!(buf, "R Function({name} {param})? {param},")?;
+        }
+        writeln!(buf, "required R Function() orElse,")?;
+        write!(buf, "}}) => switch (this)")?;
+        braced(buf, |out| {
+            for (name, param) in &members {
+                writeln!(
+                    out,
+                    "{name} $instance => {param} != null ? {param}($instance) : orElse(),"
+                )?;
+            }
+            if !sealed {
+                writeln!(out, "_ => orElse(),")?;
+            }
+            Ok(())
+        })?;
+        writeln!(buf, ";")?;
+        writeln!(buf)?;
 
-        writeln!(buf, "{modifiers} class {}Builder ", union.name)?;
-        braced(buf, |out| writeln!(out, "{} build();", union.name))?;
+        writeln!(buf, "R? mapOrNull<R>({{")?;
+        for (name, param) in &members {
+            writeln!(buf, "R Function({name} {param})? {param},")?;
+        }
+        write!(buf, "}}) => switch (this)")?;
+        braced(buf, |out| {
+            for (name, param) in &members {
+                writeln!(out, "{name} $instance => {param}?.call($instance),")?;
+            }
+            if !sealed {
+                writeln!(out, "_ => null,")?;
+            }
+            Ok(())
+        })?;
+        writeln!(buf, ";")?;
 
         Ok(())
     }
+
+    fn union_modifiers(
+        &self,
+        union: &Union,
+        modifier: Option<&SpannedScalar<ClassModifier>>,
+    ) -> String {
+        if self.library.is_sealed(union) {
+            return "sealed ".to_string();
+        }
+
+        let modifier = modifier.map_or(ClassModifier::Final, |modifier| modifier.value);
+        format!("abstract {}", modifier.prefix())
+    }
 }
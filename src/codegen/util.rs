@@ -3,26 +3,64 @@ use std::{
     process::{Command, Stdio},
 };
 
+use convert_case::{Case, Casing};
 use miette::{Diagnostic, IntoDiagnostic, NamedSource, bail};
 use thiserror::Error;
 
-use crate::model::{Library, Union};
+use crate::{
+    context::{Ty, record_fields},
+    model::{JsonRepresentation, Library, SpannedScalar, Union},
+};
 
 impl Library {
-    pub(super) fn discriminant_key_for<'lib>(&'lib self, union: &'lib Union) -> &'lib str {
+    /// Nested unions share the key of their parent, unless they specify their own
+    pub(crate) fn discriminant_key_for<'lib>(&'lib self, union: &'lib Union) -> &'lib str {
+        if let Some(key) = &union.json_discriminant {
+            return key.as_str();
+        }
+
+        if let Some(parent) = self.parent_union(union) {
+            return self.discriminant_key_for(parent);
+        }
+
+        self.defaults
+            .as_ref()
+            .and_then(|d| d.union.as_ref()?.json_discriminant.as_ref())
+            .map(|spanned| spanned.value.as_str())
+            .unwrap_or("type")
+    }
+
+    /// Nested unions share the representation of their parent, unless they specify their own
+    pub(crate) fn json_representation_for(&self, union: &Union) -> JsonRepresentation {
+        if let Some(representation) = &union.json_representation {
+            return representation.value;
+        }
+
+        if let Some(parent) = self.parent_union(union) {
+            return self.json_representation_for(parent);
+        }
+
+        self.defaults
+            .as_ref()
+            .and_then(|d| d.union.as_ref()?.json_representation.as_ref())
+            .map(|spanned| spanned.value)
+            .unwrap_or(JsonRepresentation::Internal)
+    }
+
+    pub(crate) fn json_content_key_for<'lib>(&'lib self, union: &'lib Union) -> &'lib str {
         union
-            .json_discriminant
+            .json_content_key
             .as_ref()
             .or_else(|| {
                 self.defaults
                     .as_ref()
-                    .and_then(|d| d.union.as_ref()?.json_discriminant.as_ref())
+                    .and_then(|d| d.union.as_ref()?.json_content_key.as_ref())
             })
             .map(|spanned| spanned.value.as_str())
-            .unwrap_or("type")
+            .unwrap_or("data")
     }
 
-    pub(super) fn is_sealed(&self, union: &Union) -> bool {
+    pub(crate) fn is_sealed(&self, union: &Union) -> bool {
         union
             .sealed
             .as_ref()
@@ -34,6 +72,34 @@ impl Library {
             .map(|spanned| spanned.value)
             .unwrap_or(false)
     }
+
+    pub(crate) fn generates_when(&self, union: &Union) -> bool {
+        union
+            .generate_when
+            .as_ref()
+            .or_else(|| {
+                self.defaults
+                    .as_ref()
+                    .and_then(|d| d.union.as_ref()?.generate_when.as_ref())
+            })
+            .map(|spanned| spanned.value)
+            .unwrap_or(false)
+    }
+
+    /// The direct members of a union (classes and nested unions), along with the name of the
+    /// parameter used for them in `when` and friends
+    pub(crate) fn when_members<'lib>(
+        &'lib self,
+        union: &'lib Union,
+    ) -> Vec<(&'lib SpannedScalar<String>, String)> {
+        let class_names = union.classes.iter().map(|class| &class.name);
+        let union_names = union.unions.iter().map(|nested| &nested.name);
+
+        class_names
+            .chain(union_names)
+            .map(|name| (name, name.to_case(Case::Camel)))
+            .collect()
+    }
 }
 
 /// Run `dart format` on a string
@@ -82,6 +148,135 @@ struct DartFormatError {
     stderr: String,
 }
 
+/// Writes everything in a class declaration up to (but not including) the opening brace
+pub fn class_header<W: Write>(
+    out: &mut W,
+    modifiers: &str,
+    name: &str,
+    extends: Option<&str>,
+    with: &[SpannedScalar<String>],
+    implements: &[SpannedScalar<String>],
+) -> std::fmt::Result {
+    write!(out, "{modifiers}class {name} ")?;
+
+    if let Some(extends) = extends {
+        write!(out, "extends {extends} ")?;
+    }
+
+    if !with.is_empty() {
+        let with: Vec<_> = with.iter().map(|mixin| mixin.as_str()).collect();
+        write!(out, "with {} ", with.join(", "))?;
+    }
+
+    if !implements.is_empty() {
+        let implements: Vec<_> = implements.iter().map(|ty| ty.as_str()).collect();
+        write!(out, "implements {} ", implements.join(", "))?;
+    }
+
+    Ok(())
+}
+
+/// Writes `rebuild`, which applies `updates` to a builder of the value and builds the result.
+/// Unions declare it too, so that members (whose callbacks take a narrower builder) override it
+pub fn write_rebuild<W: Write>(out: &mut W, name: &str, is_override: bool) -> std::fmt::Result {
+    if is_override {
+        writeln!(out, "@override")?;
+    }
+    writeln!(
+        out,
+        "{name} rebuild(void Function({name}Builder b) updates) => (toBuilder()..update(updates)).build();"
+    )
+}
+
+/// Writes `update`, which applies `updates` to the builder and returns it, for chaining
+pub fn write_update<W: Write>(out: &mut W, name: &str, is_override: bool) -> std::fmt::Result {
+    if is_override {
+        writeln!(out, "@override")?;
+    }
+    writeln!(
+        out,
+        "{name}Builder update(void Function({name}Builder b) updates)"
+    )?;
+    writeln!(out, "{{ updates(this); return this; }}")
+}
+
+/// Formats `s` as a double-quoted Dart string literal
+pub fn dart_string_literal(s: &str) -> String {
+    format!("\"{}\"", escape_dart_string(s))
+}
+
+/// Escapes anything that Dart would otherwise interpret inside a double-quoted string literal
+/// (quotes, backslashes, `$` interpolation and control characters)
+pub fn escape_dart_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '$' => out.push_str("\\$"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\u{0B}' => out.push_str("\\v"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{{{:X}}}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Writes a record literal with a value for each field of a record type. `value` is called with
+/// the index of each field, the name of its getter (`$1`, `$2`, ... for positional fields) and
+/// its type
+pub fn write_record_literal(
+    buf: &mut String,
+    positional: &[Ty],
+    named: &[(String, Ty)],
+    mut value: impl FnMut(&mut String, usize, &str, &Ty) -> std::fmt::Result,
+) -> std::fmt::Result {
+    write!(buf, "(")?;
+
+    for (i, (getter, ty)) in record_fields(positional, named).into_iter().enumerate() {
+        if i >= positional.len() {
+            write!(buf, "{getter}: ")?;
+        }
+        value(buf, i, &getter, ty)?;
+
+        // a single positional field needs a trailing comma to be a record
+        if i + 1 < positional.len() + named.len() || (positional.len(), named.len()) == (1, 0) {
+            write!(buf, ", ")?;
+        }
+    }
+
+    write!(buf, ")")
+}
+
+/// Splits a snippet of Dart after its `library` directive, if it starts with one (after any
+/// comments and annotations), so that imports can be written after it
+pub fn split_library_directive(dart: &str) -> (&str, &str) {
+    let mut end = 0;
+
+    for line in dart.split_inclusive('\n') {
+        end += line.len();
+
+        let line = line.trim();
+        if (line == "library;" || line.starts_with("library ")) && line.ends_with(';') {
+            return dart.split_at(end);
+        }
+        if !(line.is_empty() || line.starts_with("//") || line.starts_with('@')) {
+            break;
+        }
+    }
+
+    ("", dart)
+}
+
 pub fn braced<W: Write>(
     out: &mut W,
     f: impl FnOnce(&mut W) -> std::fmt::Result,
@@ -108,3 +303,85 @@ fn dart_format_works() {
     let formatted = dart_format(unformatted.to_string(), Some("3.8")).unwrap();
     assert_eq!(formatted.trim(), "class Foo {}");
 }
+
+#[cfg(test)]
+mod tests {
+    use proptest::property_test;
+
+    use super::*;
+
+    /// Evaluates a Dart string literal without interpolation, as produced by
+    /// [`dart_string_literal`]
+    fn unescape(literal: &str) -> String {
+        let inner = literal
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .expect("literal should be double-quoted");
+
+        let mut out = String::new();
+        let mut chars = inner.chars();
+
+        while let Some(c) = chars.next() {
+            assert_ne!(c, '"', "unescaped quote in {literal}");
+            assert_ne!(c, '$', "unescaped interpolation in {literal}");
+            assert!(!c.is_control(), "raw control character in {literal}");
+
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+
+            match chars.next().unwrap() {
+                'n' => out.push('\n'),
+                'r' => out.push('\r'),
+                't' => out.push('\t'),
+                'b' => out.push('\u{08}'),
+                'f' => out.push('\u{0C}'),
+                'v' => out.push('\u{0B}'),
+                'u' => {
+                    assert_eq!(chars.next(), Some('{'));
+                    let hex: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    let code = u32::from_str_radix(&hex, 16).unwrap();
+                    out.push(char::from_u32(code).unwrap());
+                }
+                other => out.push(other),
+            }
+        }
+
+        out
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(dart_string_literal("hello"), r#""hello""#);
+        assert_eq!(dart_string_literal("Price: $5"), r#""Price: \$5""#);
+        assert_eq!(dart_string_literal(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(dart_string_literal("a\\b"), r#""a\\b""#);
+        assert_eq!(dart_string_literal("line\nbreak"), r#""line\nbreak""#);
+        assert_eq!(dart_string_literal("\u{0}"), r#""\u{0}""#);
+        assert_eq!(dart_string_literal("🦀"), r#""🦀""#);
+    }
+
+    #[test]
+    fn splits_leading_library_directives() {
+        let dart = "// models\n@Deprecated('old')\nlibrary models;\nimport 'a.dart';\n";
+        assert_eq!(
+            split_library_directive(dart),
+            (
+                "// models\n@Deprecated('old')\nlibrary models;\n",
+                "import 'a.dart';\n"
+            )
+        );
+
+        assert_eq!(split_library_directive("library;"), ("library;", ""));
+        assert_eq!(
+            split_library_directive("class A {}\nlibrary x;"),
+            ("", "class A {}\nlibrary x;")
+        );
+    }
+
+    #[property_test]
+    fn string_literals_round_trip(s: String) {
+        assert_eq!(unescape(&dart_string_literal(&s)), s);
+    }
+}
@@ -30,10 +30,64 @@ impl Display for Ty {
             TyKind::Set(inner) => write!(f, "Set<{inner}>"),
             TyKind::Map { key, value } => write!(f, "Map<{key}, {value}>"),
             TyKind::Nullable(inner) => write!(f, "{inner}?"),
+            TyKind::Generic { name, args } => {
+                let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{name}<{}>", args.join(", "))
+            }
+            TyKind::Record { positional, named } => {
+                let mut fields: Vec<_> = positional.iter().map(|ty| ty.to_string()).collect();
+
+                if !named.is_empty() {
+                    let named: Vec<_> = named
+                        .iter()
+                        .map(|(name, ty)| format!("{ty} {name}"))
+                        .collect();
+                    fields.push(format!("{{{}}}", named.join(", ")));
+                }
+
+                // a record with a single positional field needs a trailing comma, otherwise it's
+                // just a parenthesized type
+                match (positional.len(), named.len()) {
+                    (1, 0) => write!(f, "({},)", fields[0]),
+                    _ => write!(f, "({})", fields.join(", ")),
+                }
+            }
         }
     }
 }
 
+impl Ty {
+    /// This type and every type nested inside it, outermost first
+    pub fn nested(&self) -> Vec<&Ty> {
+        let children: Vec<&Ty> = match &self.kind {
+            TyKind::Simple(_) => vec![],
+            TyKind::Nullable(inner) | TyKind::List(inner) | TyKind::Set(inner) => vec![inner],
+            TyKind::Map { key, value } => vec![key, value],
+            TyKind::Generic { args, .. } => args.iter().collect(),
+            TyKind::Record { positional, named } => positional
+                .iter()
+                .chain(named.iter().map(|(_, ty)| ty))
+                .collect(),
+        };
+
+        let mut out = vec![self];
+        out.extend(children.into_iter().flat_map(Ty::nested));
+        out
+    }
+}
+
+/// The fields of a record type, as the name of their getter (`$1`, `$2`, ... for positional
+/// fields) and their type
+pub fn record_fields<'a>(positional: &'a [Ty], named: &'a [(String, Ty)]) -> Vec<(String, &'a Ty)> {
+    let positional = positional
+        .iter()
+        .enumerate()
+        .map(|(i, ty)| (format!("${}", i + 1), ty));
+    let named = named.iter().map(|(name, ty)| (name.clone(), ty));
+
+    positional.chain(named).collect()
+}
+
 impl Context {
     pub fn parse_ty(&self, value: &SpannedScalar<String>) -> (Option<Ty>, Vec<miette::Report>) {
         let span_offset = value.span.offset();
@@ -77,7 +131,21 @@ pub enum TyKind {
     Nullable(Box<Ty>),
     List(Box<Ty>),
     Set(Box<Ty>),
-    Map { key: Box<Ty>, value: Box<Ty> },
+    Map {
+        key: Box<Ty>,
+        value: Box<Ty>,
+    },
+    /// Any other type with type arguments, such as `Future<int>` or `prefix.Optional<Foo>`. These
+    /// need a conversion from an `external-type` or the field itself
+    Generic {
+        name: String,
+        args: Vec<Ty>,
+    },
+    /// A Dart 3 record, such as `(double, double)` or `({int x, int y})`
+    Record {
+        positional: Vec<Ty>,
+        named: Vec<(String, Ty)>,
+    },
 }
 
 fn ty<'a>(span_offset: usize) -> impl Parser<'a, &'a str, Ty, Err<Rich<'a, char>>> {
@@ -122,9 +190,61 @@ fn ty<'a>(span_offset: usize) -> impl Parser<'a, &'a str, Ty, Err<Rich<'a, char>
                 value: Box::new(value),
             });
 
-        let simple = ident().map(|s| TyKind::Simple(s.to_string()));
+        let named_field = ty
+            .clone()
+            .then(ident())
+            .then_ignore(whitespace().repeated())
+            .map(|(ty, name): (Ty, &str)| (name.to_string(), ty));
+
+        let named = just('{')
+            .ignore_then(
+                named_field
+                    .separated_by(just(','))
+                    .allow_trailing()
+                    .at_least(1)
+                    .collect::<Vec<_>>(),
+            )
+            .then_ignore(whitespace().repeated())
+            .then_ignore(just('}'));
+
+        let record = just('(')
+            .ignore_then(
+                ty.clone()
+                    .separated_by(just(','))
+                    .allow_trailing()
+                    .collect::<Vec<_>>(),
+            )
+            .then_ignore(whitespace().repeated())
+            .then(named.or_not())
+            .then_ignore(whitespace().repeated())
+            .then_ignore(just(')'))
+            .map(|(positional, named)| TyKind::Record {
+                positional,
+                named: named.unwrap_or_default(),
+            });
+
+        // any other generic type, including `List`, `Set` and `Map` with the wrong number of
+        // type arguments, which is reported during validation
+        let generic = qualified_ident()
+            .then_ignore(whitespace().repeated())
+            .then_ignore(just('<'))
+            .then(
+                ty.clone()
+                    .separated_by(just(','))
+                    .allow_trailing()
+                    .at_least(1)
+                    .collect::<Vec<_>>(),
+            )
+            .then_ignore(whitespace().repeated())
+            .then_ignore(just('>'))
+            .map(|(name, args)| TyKind::Generic {
+                name: name.to_string(),
+                args,
+            });
+
+        let simple = qualified_ident().map(|s| TyKind::Simple(s.to_string()));
 
-        let all = choice((list, set, map, simple)).map_with(move |kind, extra| {
+        let all = choice((list, set, map, record, generic, simple)).map_with(move |kind, extra| {
             let span = extra.span();
             Ty {
                 kind,
@@ -154,6 +274,17 @@ fn whitespace<'a>() -> impl Parser<'a, &'a str, (), Err<Rich<'a, char>>> + Clone
     choice((just(' '), just('\n'), just('\t'))).ignored()
 }
 
+/// Whether `s` is a valid Dart identifier, ignoring reserved words. Unlike [`ident`], this
+/// rejects non-ASCII letters, which Dart doesn't allow in identifiers
+pub(crate) fn is_valid_identifier(s: &str) -> bool {
+    s.is_ascii() && ident().then_ignore(end()).parse(s).has_output()
+}
+
+/// An identifier, optionally with an import prefix (e.g. `prefix.Foo`)
+fn qualified_ident<'a>() -> impl Parser<'a, &'a str, &'a str, Err<Rich<'a, char>>> + Clone {
+    ident().then(just('.').then(ident()).or_not()).to_slice()
+}
+
 /// a modified version of [`chumsky::text::ident()`] that accepts $ as a valid char
 /// anywhere
 fn ident<'a>() -> impl Parser<'a, &'a str, &'a str, Err<Rich<'a, char>>> + Clone {
@@ -249,4 +380,80 @@ mod tests {
                 } if key.kind == TyKind::Simple("$".to_string()) 
                   && value.kind == TyKind::Simple("_$123".to_string())));
     }
+
+    #[test]
+    fn can_parse_records() {
+        let ty = parse("(double, double)");
+        assert_eq!(ty.to_string(), "(double, double)");
+
+        let ty = parse("( int , )");
+        assert!(
+            matches!(&ty.kind, TyKind::Record { positional, named } if positional.len() == 1 && named.is_empty())
+        );
+        assert_eq!(ty.to_string(), "(int,)");
+
+        let ty = parse("({int x, int y})");
+        assert!(
+            matches!(&ty.kind, TyKind::Record { positional, named } if positional.is_empty() && named[1].0 == "y")
+        );
+        assert_eq!(ty.to_string(), "({int x, int y})");
+
+        let ty = parse("(List<String>, {Map<String, int>? counts,})?");
+        assert_eq!(
+            ty.to_string(),
+            "(List<String>, {Map<String, int>? counts})?"
+        );
+
+        let ty = parse("List<(int, (String, bool))>");
+        assert_eq!(ty.to_string(), "List<(int, (String, bool))>");
+
+        let ty = parse("()");
+        assert_eq!(ty.to_string(), "()");
+
+        for invalid in ["(int", "({})", "({int})"] {
+            assert!(super::ty(0).parse(invalid).has_errors(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn can_parse_generics_and_prefixes() {
+        let ty = parse("prefix.Foo");
+        assert_eq!(ty.kind, TyKind::Simple("prefix.Foo".to_string()));
+
+        let ty = parse("Future<int>");
+        assert!(
+            matches!(&ty.kind, TyKind::Generic { name, args } if name == "Future" && args.len() == 1)
+        );
+
+        let ty = parse("x.Optional< List<x.Foo>? , >?");
+        assert_eq!(ty.to_string(), "x.Optional<List<x.Foo>?>?");
+
+        let ty = parse("Either<String, (int, int)>");
+        assert_eq!(ty.to_string(), "Either<String, (int, int)>");
+
+        // `List` and friends with the wrong number of arguments are caught during validation
+        let ty = parse("List<A, B>");
+        assert!(matches!(&ty.kind, TyKind::Generic { name, .. } if name == "List"));
+
+        let ty = parse("ListView<int>");
+        assert!(matches!(&ty.kind, TyKind::Generic { name, .. } if name == "ListView"));
+
+        for invalid in ["Foo<>", "a.b.C", ".Foo", "Foo<int"] {
+            assert!(super::ty(0).parse(invalid).has_errors(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn valid_identifiers() {
+        assert!(is_valid_identifier("foo"));
+        assert!(is_valid_identifier("_Foo123"));
+        assert!(is_valid_identifier("$"));
+
+        assert!(!is_valid_identifier(""));
+        assert!(!is_valid_identifier("123foo"));
+        assert!(!is_valid_identifier("foo bar"));
+        assert!(!is_valid_identifier("foo-bar"));
+        assert!(!is_valid_identifier("List<int>"));
+        assert!(!is_valid_identifier("café"));
+    }
 }
@@ -0,0 +1,981 @@
+use std::fmt::Display;
+
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+
+use crate::{
+    codegen::format_dart_literal_const,
+    context::{Context, Ty, TyKind, record_fields},
+    json::{Json, JsonDocument, SpannedJson, SpannedJsonKind, examples::variant_example},
+    model::*,
+    validate::closest_name,
+};
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{file}` can't be decoded as `{ty}`")]
+struct CheckJsonFailed {
+    file: String,
+    ty: String,
+
+    #[related]
+    errors: Vec<miette::Report>,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{name}` is not a class, union or enum")]
+struct UnknownJsonType {
+    name: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{reason}")]
+struct JsonMismatch {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("expected {expected}")]
+    span: SourceSpan,
+
+    reason: String,
+    expected: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{path}` violates a constraint of `{class}`")]
+struct ConstraintViolation {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("{message}")]
+    span: SourceSpan,
+
+    path: String,
+    class: String,
+    message: String,
+}
+
+impl Context {
+    /// Checks that `document` can be decoded by the generated `fromJson` of the class, union or
+    /// enum called `type_name`, reporting every value that it would reject
+    pub fn check_json(&self, document: &JsonDocument, type_name: &str) -> miette::Result<()> {
+        let known = self
+            .library
+            .type_names()
+            .any(|name| name.as_str() == type_name);
+        if !known {
+            let names: Vec<_> = self
+                .library
+                .type_names()
+                .map(|name| name.as_str())
+                .collect();
+            let help =
+                closest_name(type_name, &names).map(|name| format!("did you mean `{name}`?"));
+            return Err(UnknownJsonType {
+                name: type_name.to_string(),
+                help,
+            }
+            .into());
+        }
+
+        let errors = self.json_mismatches(&document.source, &document.root, type_name);
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(CheckJsonFailed {
+            file: document.source.name().to_string(),
+            ty: type_name.to_string(),
+            errors,
+        }
+        .into())
+    }
+
+    /// Every value in `json` that the generated `fromJson` of `type_name` would reject, where
+    /// the spans of `json` point into `source`
+    pub(crate) fn json_mismatches(
+        &self,
+        source: &NamedSource<String>,
+        json: &SpannedJson,
+        type_name: &str,
+    ) -> Vec<miette::Report> {
+        let mut checker = Checker {
+            context: self,
+            source,
+            path: vec![],
+            errors: vec![],
+        };
+        checker.named(json, type_name);
+        checker.errors
+    }
+}
+
+/// Walks a JSON document the same way the generated `fromJson` would decode it
+struct Checker<'a> {
+    context: &'a Context,
+    source: &'a NamedSource<String>,
+    /// The keys and indices leading to the value being checked
+    path: Vec<String>,
+    errors: Vec<miette::Report>,
+}
+
+impl Checker<'_> {
+    /// The path to the value being checked, such as `$.users[0].name`
+    fn path(&self) -> String {
+        format!("${}", self.path.concat())
+    }
+
+    fn in_key<T>(&mut self, key: &str, check: impl FnOnce(&mut Self) -> T) -> T {
+        let is_identifier = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+
+        match is_identifier {
+            true => self.path.push(format!(".{key}")),
+            false => self
+                .path
+                .push(format!("[{}]", Json::String(key.to_string()))),
+        }
+        let result = check(self);
+        self.path.pop();
+        result
+    }
+
+    fn in_index<T>(&mut self, index: usize, check: impl FnOnce(&mut Self) -> T) -> T {
+        self.path.push(format!("[{index}]"));
+        let result = check(self);
+        self.path.pop();
+        result
+    }
+
+    fn mismatch(
+        &mut self,
+        span: SourceSpan,
+        expected: impl Display,
+        reason: String,
+        help: Option<String>,
+    ) {
+        let err = JsonMismatch {
+            src: self.source.clone(),
+            span,
+            reason,
+            expected: expected.to_string(),
+            help,
+        };
+        self.errors.push(err.into());
+    }
+
+    fn wrong_type(&mut self, json: &SpannedJson, expected: impl Display, help: Option<String>) {
+        let reason = format!(
+            "`{}` should be `{expected}`, but it is {}",
+            self.path(),
+            describe(json)
+        );
+        self.mismatch(json.span, format!("`{expected}`"), reason, help);
+    }
+
+    /// The members of `json`, or `None` after reporting a mismatch if it isn't an object
+    fn object<'j>(
+        &mut self,
+        json: &'j SpannedJson,
+        expected: impl Display,
+    ) -> Option<&'j [(SpannedScalar<String>, SpannedJson)]> {
+        match &json.kind {
+            SpannedJsonKind::Object(members) => Some(members),
+            _ => {
+                self.wrong_type(json, expected, None);
+                None
+            }
+        }
+    }
+
+    /// Checks a value that `fromJson` reads from `object[key]`, where the key is missing
+    fn missing(&mut self, object: &SpannedJson, key: &str, ty: &Ty) {
+        if self.accepts_null(ty) {
+            return;
+        }
+
+        let keys: Vec<_> = match &object.kind {
+            SpannedJsonKind::Object(members) => {
+                members.iter().map(|(key, _)| key.as_str()).collect()
+            }
+            _ => vec![],
+        };
+        let help = closest_name(key, &keys).map(|found| {
+            format!("found `{found}` instead, which may need a `json-key` or `json-key-case`")
+        });
+
+        let reason = format!("`{}` is missing", self.path());
+        self.mismatch(
+            object.span,
+            format!("a key {}", Json::String(key.to_string())),
+            reason,
+            help,
+        );
+    }
+
+    /// Whether `fromJson` accepts `null` (or a missing key) for a value of type `ty`
+    fn accepts_null(&self, ty: &Ty) -> bool {
+        match &ty.kind {
+            TyKind::Nullable(_) => true,
+            TyKind::Simple(name) if name == "dynamic" => true,
+            // custom conversions might accept anything
+            TyKind::Simple(name) | TyKind::Generic { name, .. } => self.has_from_json(name),
+            _ => false,
+        }
+    }
+
+    fn has_from_json(&self, name: &str) -> bool {
+        self.context
+            .library
+            .external_type(name)
+            .is_some_and(|external| external.from_json.is_some())
+    }
+
+    /// Checks a class, union or enum
+    fn named(&mut self, json: &SpannedJson, name: &str) {
+        let library = &self.context.library;
+
+        if let Some(enumeration) = library.enums.iter().find(|e| e.name.as_str() == name) {
+            self.enumeration(json, enumeration);
+        } else if let Some(class) = library.all_classes().find(|c| c.name.as_str() == name) {
+            self.class(json, class);
+        } else if let Some(union) = library.all_unions().find(|u| u.name.as_str() == name) {
+            self.union(json, union);
+        }
+    }
+
+    fn enumeration(&mut self, json: &SpannedJson, enumeration: &Enum) {
+        let value = json.to_json();
+        let values: Vec<_> = enumeration.variants.iter().map(variant_example).collect();
+
+        if values.iter().any(|known| json_eq(known, &value)) {
+            return;
+        }
+
+        let values: Vec<_> = values.iter().map(Json::to_string).collect();
+        let help = format!(
+            "`{}` is written as one of {}",
+            enumeration.name,
+            values.join(", ")
+        );
+        let reason = format!(
+            "`{}` is not a variant of `{}`",
+            self.path(),
+            enumeration.name
+        );
+        self.mismatch(
+            json.span,
+            format!("`{}`", enumeration.name),
+            reason,
+            Some(help),
+        );
+    }
+
+    fn class(&mut self, json: &SpannedJson, class: &Class) {
+        let library = &self.context.library;
+
+        if self.object(json, &class.name).is_none() {
+            return;
+        }
+
+        // adjacently and externally tagged unions nest the fields inside another object, the
+        // same way as `generate_from_json`
+        let content_key = match library.union_of(class) {
+            Some(union) if !class.fields.is_empty() => match library.json_representation_for(union)
+            {
+                JsonRepresentation::Adjacent => Some(Json::String(
+                    library.json_content_key_for(union).to_string(),
+                )),
+                JsonRepresentation::External => {
+                    Some(library.discriminant_json_value_for(union, class))
+                }
+                JsonRepresentation::Internal | JsonRepresentation::Untagged => None,
+            },
+            _ => None,
+        };
+
+        let Some(content_key) = content_key else {
+            self.fields(json, class);
+            return;
+        };
+
+        // `fromJson` indexes a `Map<String, dynamic>`, so only string keys can be found
+        let content = match &content_key {
+            Json::String(key) => json.get(key),
+            _ => None,
+        };
+        let key = match &content_key {
+            Json::String(key) => key.clone(),
+            other => other.to_string(),
+        };
+
+        self.in_key(&key, |this| match content {
+            Some(content) if this.object(content, &class.name).is_some() => {
+                this.fields(content, class);
+            }
+            Some(_) => {}
+            None => {
+                let reason = format!("`{}` is missing", this.path());
+                let expected = format!("a key {}", Json::String(key.clone()));
+                this.mismatch(json.span, expected, reason, None);
+            }
+        });
+    }
+
+    /// Checks the fields of `class` in the object `json`
+    fn fields(&mut self, json: &SpannedJson, class: &Class) {
+        let library = &self.context.library;
+        let validate = self.context.validates_from_json(class);
+
+        for field in &class.fields {
+            let key = library.json_key_for(class, field);
+            let value = json.get(&key);
+
+            let has_default = field.defaults_to.is_some() || field.defaults_to_dart.is_some();
+            let is_null = value.is_none_or(|value| value.kind == SpannedJsonKind::Null);
+
+            // custom conversions can't be checked without running them
+            if (has_default && is_null) || field.from_json.is_some() {
+                continue;
+            }
+
+            let Some(ty) = self.context.parse_ty(&field.ty).0 else {
+                continue;
+            };
+            let records = library.record_representation_for(field);
+
+            self.in_key(&key, |this| {
+                let Some(value) = value else {
+                    this.missing(json, &key, &ty);
+                    return;
+                };
+
+                let errors = this.errors.len();
+                this.ty(value, &ty, records);
+
+                // constraints are only checked once the value has been decoded
+                if validate && errors == this.errors.len() {
+                    this.constraints(value, class, field);
+                }
+            });
+        }
+    }
+
+    fn union(&mut self, json: &SpannedJson, union: &Union) {
+        let library = &self.context.library;
+
+        let Some(members) = self.object(json, &union.name) else {
+            return;
+        };
+
+        match library.json_representation_for(union) {
+            JsonRepresentation::Internal | JsonRepresentation::Adjacent => {
+                let key = library.discriminant_key_for(union);
+                let entries = library.discriminant_entries(union);
+
+                let value = json.get(key);
+                let found = value.map_or(Json::Null, SpannedJson::to_json);
+
+                if let Some(entry) = entries.iter().find(|entry| json_eq(&entry.json, &found)) {
+                    self.named(json, entry.name);
+                    return;
+                }
+
+                let values: Vec<_> = entries.iter().map(|entry| entry.json.to_string()).collect();
+                let help = format!(
+                    "`{}` uses the discriminants {}",
+                    union.name,
+                    values.join(", ")
+                );
+                let expected = format!("a discriminant of `{}`", union.name);
+
+                self.in_key(key, |this| {
+                    let (span, reason) = match value {
+                        Some(value) => (
+                            value.span,
+                            format!(
+                                "`{}` is not a discriminant of `{}`",
+                                this.path(),
+                                union.name
+                            ),
+                        ),
+                        None => (json.span, format!("`{}` is missing", this.path())),
+                    };
+                    this.mismatch(span, expected, reason, Some(help));
+                });
+            }
+            JsonRepresentation::External => {
+                // `fromJson` switches on `json.keys.singleOrNull`
+                let mut keys: Vec<&SpannedScalar<String>> = vec![];
+                for (key, _) in members {
+                    if !keys.iter().any(|existing| existing.value == key.value) {
+                        keys.push(key);
+                    }
+                }
+
+                let names: Vec<_> = union
+                    .classes
+                    .iter()
+                    .map(|class| {
+                        library
+                            .discriminant_json_value_for(union, class)
+                            .to_string()
+                    })
+                    .collect();
+                let help = format!("`{}` uses the keys {}", union.name, names.join(", "));
+
+                let [key] = keys.as_slice() else {
+                    let reason = format!(
+                        "`{}` should have a single key naming a member of `{}`, but it has {}",
+                        self.path(),
+                        union.name,
+                        plural(keys.len(), "key")
+                    );
+                    self.mismatch(json.span, "a single key", reason, Some(help));
+                    return;
+                };
+
+                let found = Json::String(key.to_string());
+                let member = union
+                    .classes
+                    .iter()
+                    .find(|class| library.discriminant_json_value_for(union, class) == found);
+
+                match member {
+                    Some(class) => self.class(json, class),
+                    None => {
+                        let reason = format!("{found} is not a member of `{}`", union.name);
+                        let expected = format!("a member of `{}`", union.name);
+                        self.mismatch(key.span, expected, reason, Some(help));
+                    }
+                }
+            }
+            JsonRepresentation::Untagged => {
+                let members = self.context.untagged_members(union);
+
+                let matching = members
+                    .iter()
+                    .find(|(_, keys)| keys.iter().all(|key| json.get(key).is_some()));
+
+                if let Some((class, _)) = matching {
+                    self.class(json, class);
+                    return;
+                }
+
+                let required: Vec<_> = members
+                    .iter()
+                    .map(|(class, keys)| {
+                        let keys: Vec<_> = keys
+                            .iter()
+                            .map(|key| Json::String(key.to_string()).to_string())
+                            .collect();
+                        format!("`{}` needs {}", class.name, keys.join(", "))
+                    })
+                    .collect();
+
+                let reason = format!(
+                    "`{}` doesn't have the keys of any member of `{}`",
+                    self.path(),
+                    union.name
+                );
+                let expected = format!("`{}`", union.name);
+                self.mismatch(json.span, expected, reason, Some(required.join(", ")));
+            }
+        }
+    }
+
+    /// Checks a value of type `ty`, the same way as `write_expr_from_json`
+    fn ty(&mut self, json: &SpannedJson, ty: &Ty, records: RecordRepresentation) {
+        if json.kind == SpannedJsonKind::Null {
+            if !self.accepts_null(ty) {
+                self.wrong_type(json, ty, None);
+            }
+            return;
+        }
+
+        match &ty.kind {
+            TyKind::Nullable(inner) => self.ty(json, inner, records),
+            TyKind::Simple(name) => self.simple(json, ty, name),
+            // generic types are either converted by an external type, or cast with `as`, which
+            // can't be checked without knowing the type
+            TyKind::Generic { .. } => {}
+            TyKind::List(inner) | TyKind::Set(inner) => {
+                let SpannedJsonKind::Array(items) = &json.kind else {
+                    self.wrong_type(json, ty, None);
+                    return;
+                };
+
+                for (i, item) in items.iter().enumerate() {
+                    self.in_index(i, |this| this.ty(item, inner, records));
+                }
+            }
+            TyKind::Map { key, value } => {
+                let Some(members) = self.object(json, ty) else {
+                    return;
+                };
+
+                for (entry_key, entry_value) in members {
+                    self.map_key(entry_key, key);
+                    self.in_key(entry_key, |this| this.ty(entry_value, value, records));
+                }
+            }
+            TyKind::Record { positional, named } => {
+                let fields = record_fields(positional, named);
+
+                match records {
+                    RecordRepresentation::Array => {
+                        let SpannedJsonKind::Array(items) = &json.kind else {
+                            self.wrong_type(json, ty, None);
+                            return;
+                        };
+
+                        if items.len() < fields.len() {
+                            let reason = format!(
+                                "`{}` has {}, but `{ty}` has {}",
+                                self.path(),
+                                plural(items.len(), "item"),
+                                plural(fields.len(), "field"),
+                            );
+                            self.mismatch(json.span, format!("`{ty}`"), reason, None);
+                            return;
+                        }
+
+                        for (i, ((_, field), item)) in fields.iter().zip(items).enumerate() {
+                            self.in_index(i, |this| this.ty(item, field, records));
+                        }
+                    }
+                    RecordRepresentation::Object => {
+                        if self.object(json, ty).is_none() {
+                            return;
+                        }
+
+                        for (getter, field) in fields {
+                            self.in_key(&getter, |this| match json.get(&getter) {
+                                Some(item) => this.ty(item, field, records),
+                                None => this.missing(json, &getter, field),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn simple(&mut self, json: &SpannedJson, ty: &Ty, name: &str) {
+        if self
+            .context
+            .library
+            .type_names()
+            .any(|n| n.as_str() == name)
+        {
+            self.named(json, name);
+            return;
+        }
+
+        if self.has_from_json(name) {
+            return;
+        }
+
+        let valid = match (name, &json.kind) {
+            ("String", SpannedJsonKind::String(_)) => true,
+            ("bool", SpannedJsonKind::Bool(_)) => true,
+            ("num", SpannedJsonKind::Number(_)) => true,
+            ("int", SpannedJsonKind::Number(number)) => !is_double(number),
+            ("double", SpannedJsonKind::Number(number)) => is_double(number),
+            ("String" | "bool" | "num" | "int" | "double", _) => false,
+            // anything else is cast with `as`, which can't be checked without knowing the type
+            _ => true,
+        };
+
+        if valid {
+            return;
+        }
+
+        let help = match (name, &json.kind) {
+            ("double", SpannedJsonKind::Number(number)) => Some(format!(
+                "`jsonDecode` reads `{number}` as an `int`, which can't be cast to a `double` on \
+                 the Dart VM. Try writing `{number}.0`"
+            )),
+            _ => None,
+        };
+        self.wrong_type(json, ty, help);
+    }
+
+    /// Map keys are always strings, and are decoded the same way as `write_key_from_json`
+    fn map_key(&mut self, key: &SpannedScalar<String>, ty: &Ty) {
+        let TyKind::Simple(name) = &ty.kind else {
+            return;
+        };
+
+        let valid = match name.as_str() {
+            "int" => parse_dart_int(key).is_some(),
+            "double" => key.trim().parse::<f64>().is_ok(),
+            _ if self
+                .context
+                .library
+                .enums
+                .iter()
+                .any(|e| e.name.as_str() == name) =>
+            {
+                let json = SpannedJson {
+                    span: key.span,
+                    kind: SpannedJsonKind::String(key.to_string()),
+                };
+                self.named(&json, name);
+                return;
+            }
+            _ => true,
+        };
+
+        if !valid {
+            let reason = format!(
+                "The key {} of `{}` can't be parsed as {}",
+                Json::String(key.to_string()),
+                self.path(),
+                match name.as_str() {
+                    "int" => "an `int`",
+                    _ => "a `double`",
+                }
+            );
+            self.mismatch(key.span, format!("`{ty}`"), reason, None);
+        }
+    }
+
+    /// Checks the constraints that the generated `fromJson` checks when `validate-from-json` is
+    /// enabled. Patterns are left out, since they are Dart regular expressions
+    fn constraints(&mut self, json: &SpannedJson, class: &Class, field: &Field) {
+        let name = &field.name;
+        let mut violations = vec![];
+
+        let number = match &json.kind {
+            SpannedJsonKind::Number(number) => number.parse::<f64>().ok(),
+            _ => None,
+        };
+        let length = match &json.kind {
+            SpannedJsonKind::String(value) => Some(value.encode_utf16().count() as u64),
+            _ => None,
+        };
+        let items = match &json.kind {
+            SpannedJsonKind::Array(items) => Some(items.len() as u64),
+            SpannedJsonKind::Object(_) => match json.to_json() {
+                Json::Object(entries) => Some(entries.len() as u64),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let literal = |value: &knus::ast::Value<knus::span::Span>| match Json::from_literal(value) {
+            Json::Number(number) => number.parse::<f64>().ok(),
+            _ => None,
+        };
+
+        if let (Some(number), Some(min)) = (number, &field.min)
+            && literal(min).is_some_and(|min| number < min)
+        {
+            let min = format_dart_literal_const(min);
+            violations.push(format!("{name} must be at least {min}"));
+        }
+        if let (Some(number), Some(max)) = (number, &field.max)
+            && literal(max).is_some_and(|max| number > max)
+        {
+            let max = format_dart_literal_const(max);
+            violations.push(format!("{name} must be at most {max}"));
+        }
+        if let (Some(length), Some(min_length)) = (length, &field.min_length)
+            && length < min_length.value
+        {
+            let min_length = plural(min_length.value as usize, "character");
+            violations.push(format!("{name} must be at least {min_length} long"));
+        }
+        if let (Some(length), Some(max_length)) = (length, &field.max_length)
+            && length > max_length.value
+        {
+            let max_length = plural(max_length.value as usize, "character");
+            violations.push(format!("{name} must be at most {max_length} long"));
+        }
+        if let (Some(items), Some(min_items)) = (items, &field.min_items)
+            && items < min_items.value
+        {
+            let min_items = plural(min_items.value as usize, "item");
+            violations.push(format!("{name} must have at least {min_items}"));
+        }
+        if let (Some(items), Some(max_items)) = (items, &field.max_items)
+            && items > max_items.value
+        {
+            let max_items = plural(max_items.value as usize, "item");
+            violations.push(format!("{name} must have at most {max_items}"));
+        }
+
+        for message in violations {
+            let err = ConstraintViolation {
+                src: self.source.clone(),
+                span: json.span,
+                path: self.path(),
+                class: class.name.to_string(),
+                message,
+            };
+            self.errors.push(err.into());
+        }
+    }
+}
+
+/// Whether `jsonDecode` reads `number` as a `double` rather than an `int`
+fn is_double(number: &str) -> bool {
+    number.contains(['.', 'e', 'E'])
+}
+
+/// Compares JSON values the way Dart compares the values `jsonDecode` produces, where `1` and
+/// `1.0` are equal
+fn json_eq(a: &Json, b: &Json) -> bool {
+    match (a, b) {
+        (Json::Number(a), Json::Number(b)) => match (a.parse::<f64>(), b.parse::<f64>()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        },
+        _ => a == b,
+    }
+}
+
+/// Parses an integer the same way as Dart's `int.parse`, which allows surrounding whitespace, a
+/// sign, and a `0x` prefix for hexadecimal
+fn parse_dart_int(s: &str) -> Option<i64> {
+    let s = s.trim();
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    let magnitude = match digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+    {
+        Some(hex) if !hex.starts_with(['+', '-']) => i64::from_str_radix(hex, 16).ok()?,
+        Some(_) => return None,
+        None if digits.starts_with(['+', '-']) => return None,
+        None => digits.parse::<i64>().ok()?,
+    };
+
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+/// A description of a JSON value, for error messages
+fn describe(json: &SpannedJson) -> String {
+    match &json.kind {
+        SpannedJsonKind::Null => "null".to_string(),
+        SpannedJsonKind::Bool(value) => format!("`{value}`"),
+        SpannedJsonKind::Number(value) => format!("the number `{value}`"),
+        SpannedJsonKind::String(value) => {
+            format!("the string {}", Json::String(value.clone()))
+        }
+        SpannedJsonKind::Array(_) => "an array".to_string(),
+        SpannedJsonKind::Object(_) => "an object".to_string(),
+    }
+}
+
+fn plural(count: usize, word: &str) -> String {
+    match count {
+        1 => format!("1 {word}"),
+        _ => format!("{count} {word}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::ExampleMode;
+
+    use super::*;
+
+    /// The number of problems found when checking `json` as `ty`
+    fn mismatch_count(kdl: &str, ty: &str, json: &str) -> usize {
+        let context = Context::from_str(kdl).unwrap();
+        context.validate(true).unwrap();
+
+        let document = JsonDocument::parse("test.json", json.to_string()).unwrap();
+        match context.check_json(&document, ty) {
+            Ok(()) => 0,
+            Err(err) => err.related().map_or(1, |related| related.count()),
+        }
+    }
+
+    #[test]
+    fn checks_classes() {
+        let kdl = r#"
+            enum "Color" {
+                variant "red"
+                variant "green" { json-value 2; }
+            }
+
+            class "User" {
+                json-key-case "snake"
+
+                field "userId" type="int"
+                field "name" type="String?"
+                field "score" type="double"
+                field "tags" type="List<String>" { defaults-to { item "new"; }; }
+                field "color" type="Color"
+                field "scores" type="Map<int, double>"
+                field "point" type="(int, int)"
+                field "extra" type="dynamic"
+            }
+        "#;
+
+        let valid = r#"{
+            "user_id": 1,
+            "score": 1.5,
+            "tags": null,
+            "color": 2.0,
+            "scores": {"1": 1e3, "0x10": 2.0},
+            "point": [1, 2, 3]
+        }"#;
+        assert_eq!(mismatch_count(kdl, "User", valid), 0);
+
+        // `userId` instead of `user_id`, an int `score`, an unknown color, a key that isn't an
+        // int, a short record and a missing `tags` item type
+        let invalid = r#"{
+            "userId": 1,
+            "score": 1,
+            "tags": [1],
+            "color": "blue",
+            "scores": {"one": 1.0},
+            "point": [1]
+        }"#;
+        assert_eq!(mismatch_count(kdl, "User", invalid), 6);
+
+        assert_eq!(mismatch_count(kdl, "User", "[]"), 1);
+        assert_eq!(mismatch_count(kdl, "Color", r#""red""#), 0);
+        assert_eq!(mismatch_count(kdl, "Color", r#""green""#), 1);
+    }
+
+    #[test]
+    fn checks_unions() {
+        let kdl = include_str!(crate::test_file!(union_json_representation));
+
+        assert_eq!(
+            mismatch_count(kdl, "Internal", r#"{"type": "InternalDog", "name": "Rex"}"#),
+            0
+        );
+        assert_eq!(
+            mismatch_count(kdl, "Internal", r#"{"type": "Dog", "name": "Rex"}"#),
+            1
+        );
+        assert_eq!(
+            mismatch_count(
+                kdl,
+                "Adjacent",
+                r#"{"kind": "dog", "content": {"name": 1}}"#
+            ),
+            1
+        );
+        assert_eq!(mismatch_count(kdl, "Adjacent", r#"{"kind": "empty"}"#), 0);
+        assert_eq!(
+            mismatch_count(kdl, "External", r#"{"external_cat": {"lives": 9}}"#),
+            0
+        );
+        assert_eq!(
+            mismatch_count(
+                kdl,
+                "External",
+                r#"{"external_cat": {}, "external_dog": {}}"#
+            ),
+            1
+        );
+        assert_eq!(
+            mismatch_count(kdl, "Untagged", r#"{"name": "Tom", "lives": "nine"}"#),
+            1
+        );
+        assert_eq!(mismatch_count(kdl, "Untagged", r#"{"name": "Rex"}"#), 0);
+        assert_eq!(mismatch_count(kdl, "Untagged", r#"{"name": 1}"#), 1);
+    }
+
+    #[test]
+    fn checks_constraints() {
+        let kdl = r#"
+            class "Account" {
+                validate-from-json true
+
+                field "age" type="int" { min 0; }
+                field "name" type="String" { min-length 2; max-length 4; }
+                field "tags" type="List<String>?" { max-items 1; }
+            }
+        "#;
+
+        assert_eq!(
+            mismatch_count(kdl, "Account", r#"{"age": 1, "name": "Sam"}"#),
+            0
+        );
+        assert_eq!(
+            mismatch_count(
+                kdl,
+                "Account",
+                r#"{"age": -1, "name": "S", "tags": ["a", "b"]}"#
+            ),
+            3
+        );
+    }
+
+    #[test]
+    fn examples_satisfy_constraints() {
+        let kdl = r#"
+            class "Account" {
+                validate-from-json true
+
+                field "age" type="int" { min 18; }
+                field "score" type="double" { min 2; max 3; }
+                field "name" type="String" { min-length 8; }
+                field "code" type="String" { max-length 2; }
+                field "tags" type="List<String>" { min-items 2; max-items 3; }
+                field "ids" type="Set<int>?" { max-items 1; }
+            }
+        "#;
+        let context = Context::from_str(kdl).unwrap();
+
+        for mode in [ExampleMode::Minimal, ExampleMode::Maximal] {
+            let (_, example) = context.json_examples(mode).remove(0);
+            let example = example.unwrap();
+            let document = JsonDocument::parse("example.json", example.to_string()).unwrap();
+            context.check_json(&document, "Account").unwrap();
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_types() {
+        let context = Context::from_str(r#"class "User" {}"#).unwrap();
+        let document = JsonDocument::parse("test.json", "{}".to_string()).unwrap();
+        assert!(context.check_json(&document, "Usr").is_err());
+    }
+
+    macro_rules! examples_are_accepted {
+        ($name:ident) => {
+            #[test]
+            fn $name() {
+                let context = Context::from_str(include_str!(crate::test_file!($name))).unwrap();
+
+                for mode in [ExampleMode::Minimal, ExampleMode::Maximal] {
+                    for (name, example) in context.json_examples(mode) {
+                        let Some(example) = example else {
+                            continue;
+                        };
+                        let document =
+                            JsonDocument::parse("example.json", example.to_string()).unwrap();
+                        if let Err(err) = context.check_json(&document, name) {
+                            panic!("{name} ({mode:?}): {err:?}");
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    mod examples {
+        use super::*;
+
+        crate::all_test_files!(examples_are_accepted);
+    }
+}
@@ -0,0 +1,433 @@
+use knus::{
+    ast::{Literal, Value},
+    span::Span,
+};
+
+use crate::{
+    context::{Context, Ty, TyKind, record_fields},
+    json::Json,
+    model::*,
+};
+
+/// Past this depth, examples only contain the keys that are required, so that types which
+/// contain themselves (through a nullable type or a collection) still have finite examples
+const MAX_DEPTH: usize = 4;
+
+/// Nesting deeper than this can only come from types which contain themselves without a
+/// nullable type or collection in between, which can't be constructed at all
+const DEPTH_LIMIT: usize = 16;
+
+/// Which keys example JSON documents contain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExampleMode {
+    /// Only the keys that `fromJson` requires, with empty collections and no optional values
+    Minimal,
+    /// Every key, with nullable values and collections populated
+    Maximal,
+}
+
+impl Context {
+    /// An example JSON document for every class, union and enum, in the order they are declared.
+    /// Union members are written the way their union's `toJson` writes them, with discriminants,
+    /// and unions use their first member that has an example. Types that need a value whose JSON
+    /// isn't known (such as an external type without `from-json`) have no example
+    pub fn json_examples(&self, mode: ExampleMode) -> Vec<(&str, Option<Json>)> {
+        let classes = self
+            .library
+            .all_classes()
+            .map(|class| (class.name.as_str(), self.class_example(class, mode, 0)));
+
+        let unions = self.library.all_unions().map(|union| {
+            let example = self.union_example(union, mode, 0);
+            (union.name.as_str(), example)
+        });
+
+        let enums = self
+            .library
+            .enums
+            .iter()
+            .map(|enumeration| (enumeration.name.as_str(), enum_example(enumeration)));
+
+        classes.chain(unions).chain(enums).collect()
+    }
+
+    fn union_example(&self, union: &Union, mode: ExampleMode, depth: usize) -> Option<Json> {
+        self.library
+            .union_members(union)
+            .into_iter()
+            .find_map(|member| self.class_example(member, mode, depth))
+    }
+
+    fn class_example(&self, class: &Class, mode: ExampleMode, depth: usize) -> Option<Json> {
+        if depth > DEPTH_LIMIT {
+            return None;
+        }
+
+        let mode = match depth > MAX_DEPTH {
+            true => ExampleMode::Minimal,
+            false => mode,
+        };
+
+        let required_keys = self.required_json_keys(class);
+        let mut fields = vec![];
+        for field in &class.fields {
+            let key = self.library.json_key_for(class, field);
+
+            // the JSON of custom conversions is unknown, so they are left out when possible
+            let optional = !required_keys.contains(&key);
+            if optional && (mode == ExampleMode::Minimal || field.to_json.is_some()) {
+                continue;
+            }
+
+            match self.field_example(field, mode, depth) {
+                Some(example) => fields.push((key.into_owned(), example)),
+                None if optional => {}
+                None => return None,
+            }
+        }
+        let fields = Json::Object(fields);
+
+        let Some(union) = self.library.union_of(class) else {
+            return Some(fields);
+        };
+
+        // the same layout as `toJson` in `generate_to_json`
+        let discriminant_key = self.library.discriminant_key_for(union).to_string();
+        let discriminant_value = self.library.discriminant_json_value_for(union, class);
+
+        let example = match self.library.json_representation_for(union) {
+            JsonRepresentation::Internal => {
+                let Json::Object(mut fields) = fields else {
+                    unreachable!()
+                };
+                for (key, value) in self.library.discriminants_json_for(union, class) {
+                    fields.push((key.to_string(), value));
+                }
+                Json::Object(fields)
+            }
+            JsonRepresentation::Adjacent => {
+                let content_key = self.library.json_content_key_for(union).to_string();
+                Json::Object(vec![
+                    (discriminant_key, discriminant_value),
+                    (content_key, fields),
+                ])
+            }
+            JsonRepresentation::External => {
+                let key = match discriminant_value {
+                    Json::String(key) | Json::Number(key) => key,
+                    other => other.to_string(),
+                };
+                Json::Object(vec![(key, fields)])
+            }
+            JsonRepresentation::Untagged => fields,
+        };
+
+        Some(example)
+    }
+
+    /// Fields use their `defaults-to`, unless it is null, since the maximal example should show
+    /// a value for every key
+    fn field_example(&self, field: &Field, mode: ExampleMode, depth: usize) -> Option<Json> {
+        let ty = self.parse_ty(&field.ty).0?;
+
+        // `fromJson` hands the value to the conversion, which might accept anything
+        if field.from_json.is_some() {
+            return Some(Json::Null);
+        }
+
+        if let Some(default) = &field.defaults_to {
+            let default = self.default_example(&ty, default);
+            if default != Json::Null {
+                return Some(default);
+            }
+        }
+
+        // minimal examples have empty collections, which are too short for `min-items`
+        let mode = match field.min_items {
+            Some(_) => ExampleMode::Maximal,
+            None => mode,
+        };
+
+        let records = self.library.record_representation_for(field);
+        let example = self.ty_example(&ty, records, mode, depth)?;
+        Some(constrained_example(field, &ty, example))
+    }
+
+    fn ty_example(
+        &self,
+        ty: &Ty,
+        records: RecordRepresentation,
+        mode: ExampleMode,
+        depth: usize,
+    ) -> Option<Json> {
+        let maximal = mode == ExampleMode::Maximal;
+
+        // when the inner value has no example, the empty value still does
+        let example = match &ty.kind {
+            TyKind::Nullable(inner) if maximal => self
+                .ty_example(inner, records, mode, depth)
+                .unwrap_or(Json::Null),
+            TyKind::Nullable(_) => Json::Null,
+            TyKind::List(inner) | TyKind::Set(inner) => match maximal {
+                true => Json::Array(
+                    self.ty_example(inner, records, mode, depth)
+                        .into_iter()
+                        .collect(),
+                ),
+                false => Json::Array(vec![]),
+            },
+            TyKind::Map { key, value } => {
+                let entry = match (maximal, self.key_example(key)) {
+                    (true, Some(key)) => self
+                        .ty_example(value, records, mode, depth)
+                        .map(|value| (key, value)),
+                    _ => None,
+                };
+                Json::Object(entry.into_iter().collect())
+            }
+            TyKind::Record { positional, named } => {
+                let fields = record_fields(positional, named)
+                    .into_iter()
+                    .map(|(getter, ty)| Some((getter, self.ty_example(ty, records, mode, depth)?)))
+                    .collect::<Option<Vec<_>>>()?;
+
+                match records {
+                    RecordRepresentation::Array => {
+                        Json::Array(fields.into_iter().map(|(_, value)| value).collect())
+                    }
+                    RecordRepresentation::Object => Json::Object(fields),
+                }
+            }
+            TyKind::Simple(name) => return self.named_example(name, mode, depth),
+            // the JSON of generic types depends on their conversions, which can't be known
+            TyKind::Generic { name, .. } => return self.unknown_example(name),
+        };
+
+        Some(example)
+    }
+
+    fn named_example(&self, name: &str, mode: ExampleMode, depth: usize) -> Option<Json> {
+        match name {
+            "String" => return Some(Json::String("string".into())),
+            "int" | "num" => return Some(Json::Number("1".into())),
+            "double" => return Some(Json::Number("1.5".into())),
+            "bool" => return Some(Json::Bool(true)),
+            _ => {}
+        }
+
+        if let Some(enumeration) = self.library.enums.iter().find(|e| e.name.as_str() == name) {
+            return enum_example(enumeration);
+        }
+
+        if let Some(class) = self.library.all_classes().find(|c| c.name.as_str() == name) {
+            return self.class_example(class, mode, depth + 1);
+        }
+
+        if let Some(union) = self.library.all_unions().find(|u| u.name.as_str() == name) {
+            return self.union_example(union, mode, depth + 1);
+        }
+
+        self.unknown_example(name)
+    }
+
+    /// Types whose JSON isn't known only have an example if `fromJson` accepts anything for
+    /// them, which is `null` for `dynamic` and external types with a `from-json`
+    fn unknown_example(&self, name: &str) -> Option<Json> {
+        let accepts_anything = name == "dynamic"
+            || self
+                .library
+                .external_type(name)
+                .is_some_and(|external| external.from_json.is_some());
+
+        accepts_anything.then_some(Json::Null)
+    }
+
+    /// Map keys are written as strings, the same way as `write_key_to_json`
+    fn key_example(&self, key: &Ty) -> Option<String> {
+        let TyKind::Simple(name) = &key.kind else {
+            return None;
+        };
+
+        match self.named_example(name, ExampleMode::Minimal, 0)? {
+            Json::String(key) | Json::Number(key) if self.library.external_type(name).is_none() => {
+                Some(key)
+            }
+            _ => None,
+        }
+    }
+
+    /// The JSON that a field with the default `default` is written as
+    fn default_example(&self, ty: &Ty, default: &DefaultValue) -> Json {
+        if let Some(value) = default.value() {
+            return self.default_scalar_example(ty, value);
+        }
+
+        let ty = match &ty.kind {
+            TyKind::Nullable(inner) => inner,
+            _ => ty,
+        };
+
+        match &ty.kind {
+            TyKind::List(inner) | TyKind::Set(inner) => Json::Array(
+                default
+                    .items
+                    .iter()
+                    .map(|item| self.default_example(inner, item))
+                    .collect(),
+            ),
+            TyKind::Map { key, value } => Json::Object(
+                default
+                    .entries
+                    .iter()
+                    .map(|entry| {
+                        let key = match self.default_scalar_example(key, &entry.key) {
+                            Json::String(key) | Json::Number(key) => key,
+                            other => other.to_string(),
+                        };
+                        (key, self.default_example(value, &entry.to_default_value()))
+                    })
+                    .collect(),
+            ),
+            TyKind::Simple(name) => {
+                let Some(class) = self.library.all_classes().find(|c| c.name.as_str() == name)
+                else {
+                    return Json::Null;
+                };
+
+                let fields = class
+                    .fields
+                    .iter()
+                    .filter_map(|field| {
+                        let ty = self.parse_ty(&field.ty).0?;
+                        let assigned = default.fields.iter().find(|assignment| {
+                            matches!(&*assignment.key.literal, Literal::String(name) if **name == *field.name)
+                        });
+
+                        let value = match (assigned, &field.defaults_to) {
+                            (Some(assignment), _) => {
+                                self.default_example(&ty, &assignment.to_default_value())
+                            }
+                            (None, Some(default)) => self.default_example(&ty, default),
+                            (None, None) => Json::Null,
+                        };
+
+                        let key = self.library.json_key_for(class, field).into_owned();
+                        Some((key, value))
+                    })
+                    .collect();
+
+                Json::Object(fields)
+            }
+            _ => Json::Null,
+        }
+    }
+
+    fn default_scalar_example(&self, ty: &Ty, value: &Value<Span>) -> Json {
+        if let Literal::String(variant) = &*value.literal
+            && let Some(enumeration) = self.ty_enum(ty)
+            && let Some(variant) = enumeration
+                .variants
+                .iter()
+                .find(|v| v.name.as_str() == &**variant)
+        {
+            return variant_example(variant);
+        }
+
+        let ty = match &ty.kind {
+            TyKind::Nullable(inner) => inner,
+            _ => ty,
+        };
+
+        // int literals are doubles in `double` fields, which `toJson` writes with a decimal point
+        match (&ty.kind, Json::from_literal(value)) {
+            (TyKind::Simple(name), Json::Number(number))
+                if name == "double" && !number.contains(['.', 'e', 'E']) =>
+            {
+                Json::Number(format!("{number}.0"))
+            }
+            (_, json) => json,
+        }
+    }
+}
+
+/// Moves `example` within the constraints of `field`, the same ones that `check-json` checks.
+/// Patterns are left out, since they are Dart regular expressions
+fn constrained_example(field: &Field, ty: &Ty, example: Json) -> Json {
+    let bound = |value: &Value<Span>| match Json::from_literal(value) {
+        Json::Number(number) => number.parse::<f64>().ok().map(|parsed| (number, parsed)),
+        _ => None,
+    };
+
+    match example {
+        Json::Number(number) => {
+            let value = number.parse::<f64>().unwrap_or_default();
+            let below = field
+                .min
+                .as_ref()
+                .and_then(bound)
+                .filter(|(_, min)| value < *min);
+            let above = field
+                .max
+                .as_ref()
+                .and_then(bound)
+                .filter(|(_, max)| value > *max);
+
+            match below.or(above) {
+                // the same as `default_scalar_example`, `double` fields need a decimal point
+                Some((bound, _))
+                    if number.contains(['.', 'e', 'E']) && !bound.contains(['.', 'e', 'E']) =>
+                {
+                    Json::Number(format!("{bound}.0"))
+                }
+                Some((bound, _)) => Json::Number(bound),
+                None => Json::Number(number),
+            }
+        }
+        Json::String(value) => {
+            let min = field
+                .min_length
+                .as_ref()
+                .map_or(0, |min| min.value as usize);
+            let max = field
+                .max_length
+                .as_ref()
+                .map_or(usize::MAX, |max| max.value as usize);
+            let length = value.chars().count().clamp(min, max.max(min));
+            Json::String(value.chars().cycle().take(length).collect())
+        }
+        Json::Array(mut items) => {
+            if let Some(max_items) = &field.max_items {
+                items.truncate(max_items.value as usize);
+            }
+
+            // repeated items would be merged in a set
+            let ty = match &ty.kind {
+                TyKind::Nullable(inner) => inner,
+                _ => ty,
+            };
+            if let (Some(min_items), Some(item), false) = (
+                &field.min_items,
+                items.first().cloned(),
+                matches!(ty.kind, TyKind::Set(_)),
+            ) {
+                let length = items.len().max(min_items.value as usize);
+                items.resize(length, item);
+            }
+
+            Json::Array(items)
+        }
+        other => other,
+    }
+}
+
+/// The JSON of the first variant of an enum
+fn enum_example(enumeration: &Enum) -> Option<Json> {
+    enumeration.variants.first().map(variant_example)
+}
+
+pub(super) fn variant_example(variant: &EnumVariant) -> Json {
+    match &variant.json_value {
+        Some(value) => Json::from_literal(value),
+        None => Json::String(variant.name.as_str().to_string()),
+    }
+}
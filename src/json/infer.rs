@@ -0,0 +1,531 @@
+use std::{collections::VecDeque, fmt::Write};
+
+use convert_case::{Case, Casing};
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+
+use crate::{
+    context::is_valid_identifier,
+    json::{JsonDocument, SpannedJson, SpannedJsonKind},
+    model::SpannedScalar,
+    validate::{
+        BUILT_IN_IDENTIFIERS, DART_CORE_TYPES, GENERATED_MEMBERS, OBJECT_MEMBERS, RESERVED_WORDS,
+    },
+};
+
+/// Everything seen in the values at one place in the samples
+#[derive(Debug, Default)]
+struct Shape {
+    /// Whether any of the values were `null`
+    nullable: bool,
+    kind: ShapeKind,
+}
+
+#[derive(Debug, Default)]
+enum ShapeKind {
+    /// Only `null`s, or no values at all, such as the items of empty lists
+    #[default]
+    Unknown,
+    Bool,
+    Int,
+    Double,
+    /// Both ints and doubles
+    Num,
+    String,
+    List(Box<Shape>),
+    Object(ObjectShape),
+    /// Values of more than one kind
+    Mixed,
+}
+
+#[derive(Debug, Default)]
+struct ObjectShape {
+    /// The number of objects that were merged into this shape
+    samples: usize,
+    keys: Vec<KeyShape>,
+}
+
+#[derive(Debug)]
+struct KeyShape {
+    key: String,
+    shape: Shape,
+    /// The number of objects that had this key
+    samples: usize,
+}
+
+impl Shape {
+    fn of(json: &SpannedJson) -> Shape {
+        let kind = match &json.kind {
+            SpannedJsonKind::Null => {
+                return Shape {
+                    nullable: true,
+                    kind: ShapeKind::Unknown,
+                };
+            }
+            SpannedJsonKind::Bool(_) => ShapeKind::Bool,
+            SpannedJsonKind::Number(number) if number.contains(['.', 'e', 'E']) => {
+                ShapeKind::Double
+            }
+            SpannedJsonKind::Number(_) => ShapeKind::Int,
+            SpannedJsonKind::String(_) => ShapeKind::String,
+            SpannedJsonKind::Array(items) => {
+                let item = items
+                    .iter()
+                    .map(Shape::of)
+                    .fold(Shape::default(), Shape::merge);
+                ShapeKind::List(Box::new(item))
+            }
+            SpannedJsonKind::Object(members) => ShapeKind::Object(ObjectShape::of(members)),
+        };
+
+        Shape {
+            nullable: false,
+            kind,
+        }
+    }
+
+    fn merge(self, other: Shape) -> Shape {
+        let kind = match (self.kind, other.kind) {
+            (ShapeKind::Unknown, kind) | (kind, ShapeKind::Unknown) => kind,
+            (ShapeKind::Int | ShapeKind::Double | ShapeKind::Num, ShapeKind::Num)
+            | (ShapeKind::Num, ShapeKind::Int | ShapeKind::Double)
+            | (ShapeKind::Int, ShapeKind::Double)
+            | (ShapeKind::Double, ShapeKind::Int) => ShapeKind::Num,
+            (ShapeKind::List(a), ShapeKind::List(b)) => ShapeKind::List(Box::new(a.merge(*b))),
+            (ShapeKind::Object(a), ShapeKind::Object(b)) => ShapeKind::Object(a.merge(b)),
+            (a, b) if std::mem::discriminant(&a) == std::mem::discriminant(&b) => a,
+            _ => ShapeKind::Mixed,
+        };
+
+        Shape {
+            nullable: self.nullable || other.nullable,
+            kind,
+        }
+    }
+}
+
+impl ObjectShape {
+    fn of(members: &[(SpannedScalar<String>, SpannedJson)]) -> ObjectShape {
+        let mut keys: Vec<KeyShape> = vec![];
+
+        for (key, value) in members {
+            let shape = Shape::of(value);
+
+            // `jsonDecode` keeps the last value of a duplicated key
+            match keys.iter_mut().find(|k| k.key == **key) {
+                Some(existing) => existing.shape = shape,
+                None => keys.push(KeyShape {
+                    key: key.to_string(),
+                    shape,
+                    samples: 1,
+                }),
+            }
+        }
+
+        ObjectShape { samples: 1, keys }
+    }
+
+    fn merge(mut self, other: ObjectShape) -> ObjectShape {
+        self.samples += other.samples;
+
+        for key in other.keys {
+            match self.keys.iter().position(|k| k.key == key.key) {
+                Some(i) => {
+                    let existing = &mut self.keys[i];
+                    existing.shape = std::mem::take(&mut existing.shape).merge(key.shape);
+                    existing.samples += key.samples;
+                }
+                None => self.keys.push(key),
+            }
+        }
+
+        self
+    }
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Can't infer a class from this JSON")]
+#[diagnostic(help = "Classes can only be inferred from an object, or an array of objects")]
+struct NotAnObject {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("expected an object")]
+    span: SourceSpan,
+}
+
+/// Infers a KDL config from sample JSON documents. The root class, called `root_name`, merges
+/// every document, or every item of documents which are arrays, so keys missing from some
+/// samples become nullable fields with `defaults-to null`. Nested objects become classes named
+/// after their key, and objects under keys with the same name share a class
+pub fn infer_kdl(documents: &[JsonDocument], root_name: &str) -> miette::Result<String> {
+    let mut root = ObjectShape::default();
+
+    for document in documents {
+        let samples = match &document.root.kind {
+            SpannedJsonKind::Array(items) => items.iter().collect(),
+            _ => vec![&document.root],
+        };
+
+        for sample in samples {
+            let SpannedJsonKind::Object(members) = &sample.kind else {
+                return Err(NotAnObject {
+                    src: document.source.clone(),
+                    span: sample.span,
+                }
+                .into());
+            };
+            root = root.merge(ObjectShape::of(members));
+        }
+    }
+
+    let classes = collect_classes(class_name(root_name, false), root);
+
+    let mut kdl = String::new();
+    let names: Vec<_> = documents
+        .iter()
+        .map(|document| format!("`{}`", document.source.name()))
+        .collect();
+    writeln!(kdl, "// Inferred from {}", names.join(", ")).expect("writing to a string");
+
+    for (name, class) in &classes {
+        writeln!(kdl).expect("writing to a string");
+        write_class(&mut kdl, name, class).expect("writing to a string");
+    }
+
+    Ok(kdl)
+}
+
+/// Every class, breadth first from the root, with the shapes of classes that share a name
+/// merged together
+fn collect_classes(root_name: String, root: ObjectShape) -> Vec<(String, ObjectShape)> {
+    let mut classes: Vec<(String, ObjectShape)> = vec![];
+    let mut queue = VecDeque::from([(root_name, root)]);
+
+    while let Some((name, mut object)) = queue.pop_front() {
+        for key in &mut object.keys {
+            let mut shape = &mut key.shape;
+            let mut item = false;
+            loop {
+                match &mut shape.kind {
+                    ShapeKind::List(inner) => {
+                        shape = inner;
+                        item = true;
+                    }
+                    ShapeKind::Object(nested) => {
+                        queue.push_back((class_name(&key.key, item), std::mem::take(nested)));
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        match classes.iter().position(|(existing, _)| *existing == name) {
+            Some(i) => {
+                let existing = std::mem::take(&mut classes[i].1);
+                classes[i].1 = existing.merge(object);
+            }
+            None => classes.push((name, object)),
+        }
+    }
+
+    classes
+}
+
+fn write_class(kdl: &mut String, name: &str, class: &ObjectShape) -> std::fmt::Result {
+    if class.keys.is_empty() {
+        return writeln!(kdl, "class {} {{}}", kdl_string(name));
+    }
+
+    writeln!(kdl, "class {} {{", kdl_string(name))?;
+
+    let mut field_names: Vec<String> = vec![];
+    for key in &class.keys {
+        let base_name = field_name(&key.key);
+        let mut field_name = base_name.clone();
+        let mut n = 2;
+        while field_names.contains(&field_name) {
+            field_name = format!("{base_name}{n}");
+            n += 1;
+        }
+
+        let optional = key.samples < class.samples;
+        let ty = match (ty(&key.shape, &key.key, false), optional) {
+            (ty, true) if !ty.ends_with('?') => format!("{ty}?"),
+            (ty, _) => ty,
+        };
+
+        let mut children = vec![];
+        if field_name != key.key {
+            children.push(format!("json-key {}", kdl_string(&key.key)));
+        }
+        if optional {
+            children.push("defaults-to null".to_string());
+        }
+
+        write!(
+            kdl,
+            "  field {} type={}",
+            kdl_string(&field_name),
+            kdl_string(&ty)
+        )?;
+        match children.as_slice() {
+            [] => writeln!(kdl)?,
+            children => {
+                writeln!(kdl, " {{")?;
+                for child in children {
+                    writeln!(kdl, "    {child}")?;
+                }
+                writeln!(kdl, "  }}")?;
+            }
+        }
+
+        field_names.push(field_name);
+    }
+
+    writeln!(kdl, "}}")
+}
+
+/// The Dart type of a shape. Objects use the class named after `key`, and `item` is whether the
+/// shape is inside a list
+fn ty(shape: &Shape, key: &str, item: bool) -> String {
+    let ty = match &shape.kind {
+        // `Object?` can hold any JSON, including `null`
+        ShapeKind::Unknown | ShapeKind::Mixed => return "Object?".to_string(),
+        ShapeKind::Bool => "bool".to_string(),
+        ShapeKind::Int => "int".to_string(),
+        ShapeKind::Double => "double".to_string(),
+        ShapeKind::Num => "num".to_string(),
+        ShapeKind::String => "String".to_string(),
+        ShapeKind::List(inner) => format!("List<{}>", self::ty(inner, key, true)),
+        ShapeKind::Object(_) => class_name(key, item),
+    };
+
+    match shape.nullable {
+        true => format!("{ty}?"),
+        false => ty,
+    }
+}
+
+/// A PascalCase class name for the objects under `key`, in the singular if they are the items
+/// of a list
+fn class_name(key: &str, item: bool) -> String {
+    let mut name = words(key).to_case(Case::Pascal);
+
+    if item {
+        name = singular(&name);
+    }
+
+    if name.is_empty() || !is_valid_identifier(&name) {
+        name = format!("Data {name}").to_case(Case::Pascal);
+    }
+
+    let taken = DART_CORE_TYPES
+        .iter()
+        .chain(RESERVED_WORDS)
+        .chain(BUILT_IN_IDENTIFIERS)
+        .any(|taken| taken.eq_ignore_ascii_case(&name));
+    if taken {
+        name.push_str("Data");
+    }
+
+    name
+}
+
+/// A camelCase field name for `key`
+fn field_name(key: &str) -> String {
+    let mut name = words(key).to_case(Case::Camel);
+
+    if name.is_empty() || !is_valid_identifier(&name) {
+        name = format!("value {name}").to_case(Case::Camel);
+    }
+
+    let taken = RESERVED_WORDS
+        .iter()
+        .chain(OBJECT_MEMBERS)
+        .chain(GENERATED_MEMBERS)
+        .any(|taken| *taken == name);
+    if taken {
+        name.push_str("Value");
+    }
+
+    name
+}
+
+/// The words of a JSON key, separated by spaces, without any characters that can't be used in a
+/// Dart identifier
+fn words(key: &str) -> String {
+    key.chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => ' ',
+        })
+        .collect()
+}
+
+/// A naive singular form of a PascalCase name, such as `Category` for `Categories`
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies")
+        && !stem.is_empty()
+    {
+        return format!("{stem}y");
+    }
+
+    for suffix in ["sses", "xes", "ches", "shes"] {
+        if name.ends_with(suffix) {
+            return name[..name.len() - 2].to_string();
+        }
+    }
+
+    match name.strip_suffix('s') {
+        Some(stem) if !stem.is_empty() && !stem.ends_with('s') => stem.to_string(),
+        _ => format!("{name}Item"),
+    }
+}
+
+/// A KDL string literal
+fn kdl_string(s: &str) -> String {
+    let mut out = String::from('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                write!(out, "\\u{{{:x}}}", c as u32).expect("writing to a string")
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::Context;
+
+    use super::*;
+
+    fn infer(samples: &[&str]) -> String {
+        let documents: Vec<_> = samples
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| {
+                JsonDocument::parse(&format!("sample{}.json", i + 1), sample.to_string()).unwrap()
+            })
+            .collect();
+
+        let kdl = infer_kdl(&documents, "response").unwrap();
+
+        let context = Context::from_str(&kdl).unwrap();
+        if let Err(err) = context.validate(true) {
+            panic!("inferred KDL failed validation: {err:?}\n{kdl}");
+        }
+
+        // the inferred config should accept every sample it was inferred from
+        for document in &documents {
+            let samples = match &document.root.kind {
+                SpannedJsonKind::Array(items) => items.iter().collect(),
+                _ => vec![&document.root],
+            };
+
+            for sample in samples {
+                let errors = context.json_mismatches(&document.source, sample, "Response");
+                if !errors.is_empty() {
+                    panic!("inferred KDL rejected a sample: {errors:?}\n{kdl}");
+                }
+            }
+        }
+
+        kdl
+    }
+
+    #[test]
+    fn infers_classes() {
+        let kdl = infer(&[
+            r#"{
+                "id": 1,
+                "display_name": "Rex",
+                "score": 1,
+                "nickname": null,
+                "tags": ["good", "dog"],
+                "owner": {"name": "Sam", "address": {"street": "Main St"}},
+                "categories": [{"id": 1, "label": "pets"}],
+                "history": []
+            }"#,
+            r#"[{
+                "id": 2,
+                "display_name": "Fido",
+                "score": 2.5,
+                "nickname": "Fi",
+                "tags": [],
+                "owner": {"name": "Alex", "phone": "555"},
+                "categories": [{"id": 2}],
+                "history": [],
+                "extra": true
+            }]"#,
+        ]);
+
+        insta::assert_snapshot!(kdl);
+    }
+
+    #[test]
+    fn infers_valid_names() {
+        let kdl = infer(&[r#"{
+            "class": 1,
+            "hashCode": 2,
+            "toJson": 3,
+            "user-id": 4,
+            "userId": 5,
+            "$price": 6,
+            "2fa": true,
+            "": "empty",
+            "Ünïcode": "x",
+            "say \"hi\"": "hi",
+            "mixed": [1, "two", null],
+            "matrix": [[1, 2], [3]],
+            "error": {"message": "oops"},
+            "type": {"name": "dog"},
+            "boxes": [{"size": 1}],
+            "data": [{"size": 2}],
+            "list": [{}],
+            "unknown": null
+        }"#]);
+
+        insta::assert_snapshot!(kdl);
+    }
+
+    #[test]
+    fn merges_classes_with_the_same_name() {
+        let kdl = infer(&[r#"{
+            "author": {"name": "Sam"},
+            "comments": [{"author": {"name": "Alex", "avatar": "alex.png"}}]
+        }"#]);
+
+        insta::assert_snapshot!(kdl);
+    }
+
+    #[test]
+    fn merges_ints_and_doubles_into_num() {
+        let kdl = infer(&[
+            r#"{"price": 1, "ratio": 0.5, "counts": [1, 2.5]}"#,
+            r#"{"price": 2.5, "ratio": 1, "counts": []}"#,
+        ]);
+
+        assert!(kdl.contains(r#"field "price" type="num""#), "{kdl}");
+        assert!(kdl.contains(r#"field "ratio" type="num""#), "{kdl}");
+        assert!(kdl.contains(r#"field "counts" type="List<num>""#), "{kdl}");
+    }
+
+    #[test]
+    fn rejects_non_objects() {
+        let document = JsonDocument::parse("sample.json", "[{}, 1]".to_string()).unwrap();
+        assert!(infer_kdl(&[document], "Response").is_err());
+    }
+}
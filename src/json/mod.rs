@@ -0,0 +1,150 @@
+//! A small JSON representation, used for the JSON that is read and written alongside the
+//! generated Dart
+
+use std::fmt::{Display, Formatter, Write};
+
+use knus::{
+    ast::{Decimal, Integer, Literal, Radix, Value},
+    span::Span,
+};
+
+mod check;
+mod examples;
+mod infer;
+mod parse;
+
+pub use examples::ExampleMode;
+pub use infer::infer_kdl;
+pub use parse::{JsonDocument, SpannedJson, SpannedJsonKind, parse_embedded};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    /// Numbers are kept as they are written
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    /// Objects keep their keys in the order they were inserted
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// The JSON equivalent of a KDL literal
+    pub fn from_literal(value: &Value<Span>) -> Json {
+        match &*value.literal {
+            Literal::Null => Json::Null,
+            Literal::Bool(value) => Json::Bool(*value),
+            Literal::Int(Integer(radix, str)) => {
+                let radix = match radix {
+                    Radix::Bin => 2,
+                    Radix::Oct => 8,
+                    Radix::Dec => 10,
+                    Radix::Hex => 16,
+                };
+                match i128::from_str_radix(str, radix) {
+                    Ok(value) => Json::Number(value.to_string()),
+                    Err(_) => Json::Number(str.to_string()),
+                }
+            }
+            Literal::Decimal(Decimal(str)) => Json::Number(str.to_string()),
+            Literal::String(str) => Json::String(str.to_string()),
+        }
+    }
+
+    fn write_indented(&self, f: &mut Formatter<'_>, indent: usize) -> std::fmt::Result {
+        let pad = |f: &mut Formatter<'_>, indent: usize| write!(f, "{:1$}", "", indent * 2);
+
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_json_string(f, value),
+            Json::Array(items) if items.is_empty() => write!(f, "[]"),
+            Json::Array(items) => {
+                writeln!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    pad(f, indent + 1)?;
+                    item.write_indented(f, indent + 1)?;
+                    if i + 1 < items.len() {
+                        write!(f, ",")?;
+                    }
+                    writeln!(f)?;
+                }
+                pad(f, indent)?;
+                write!(f, "]")
+            }
+            Json::Object(entries) if entries.is_empty() => write!(f, "{{}}"),
+            Json::Object(entries) => {
+                writeln!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    pad(f, indent + 1)?;
+                    write_json_string(f, key)?;
+                    write!(f, ": ")?;
+                    value.write_indented(f, indent + 1)?;
+                    if i + 1 < entries.len() {
+                        write!(f, ",")?;
+                    }
+                    writeln!(f)?;
+                }
+                pad(f, indent)?;
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Pretty-prints the JSON, indented with two spaces
+impl Display for Json {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+fn write_json_string(f: &mut impl Write, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+
+    f.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pretty_prints() {
+        let json = Json::Object(vec![
+            ("name".into(), Json::String("Rex \"the dog\"\n".into())),
+            ("age".into(), Json::Number("3".into())),
+            (
+                "tags".into(),
+                Json::Array(vec![Json::Bool(true), Json::Null]),
+            ),
+            ("empty".into(), Json::Object(vec![])),
+        ]);
+
+        let expected = r#"{
+  "name": "Rex \"the dog\"\n",
+  "age": 3,
+  "tags": [
+    true,
+    null
+  ],
+  "empty": {}
+}"#;
+
+        assert_eq!(json.to_string(), expected);
+    }
+}
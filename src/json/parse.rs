@@ -0,0 +1,370 @@
+use std::path::Path;
+
+use chumsky::{extra::Err, prelude::*};
+use miette::{Diagnostic, IntoDiagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+
+use crate::{json::Json, model::SpannedScalar};
+
+/// A JSON value, along with the span of the value and of every key inside it, so that
+/// diagnostics can point into the JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedJson {
+    pub span: SourceSpan,
+    pub kind: SpannedJsonKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedJsonKind {
+    Null,
+    Bool(bool),
+    /// Numbers are kept as they are written
+    Number(String),
+    String(String),
+    Array(Vec<SpannedJson>),
+    /// Objects keep their keys in the order they are written, including duplicates
+    Object(Vec<(SpannedScalar<String>, SpannedJson)>),
+}
+
+impl SpannedJson {
+    /// The same value without spans. Duplicated keys keep their last value, like `jsonDecode`
+    pub fn to_json(&self) -> Json {
+        match &self.kind {
+            SpannedJsonKind::Null => Json::Null,
+            SpannedJsonKind::Bool(value) => Json::Bool(*value),
+            SpannedJsonKind::Number(value) => Json::Number(value.clone()),
+            SpannedJsonKind::String(value) => Json::String(value.clone()),
+            SpannedJsonKind::Array(items) => Json::Array(items.iter().map(Self::to_json).collect()),
+            SpannedJsonKind::Object(members) => {
+                let mut entries: Vec<(String, Json)> = vec![];
+                for (key, value) in members {
+                    entries.retain(|(existing, _)| *existing != **key);
+                    entries.push((key.to_string(), value.to_json()));
+                }
+                Json::Object(entries)
+            }
+        }
+    }
+
+    /// The value of `key`, if this is an object that has it. Duplicated keys use their last
+    /// value, like `jsonDecode`
+    pub fn get(&self, key: &str) -> Option<&SpannedJson> {
+        match &self.kind {
+            SpannedJsonKind::Object(members) => members
+                .iter()
+                .rev()
+                .find(|(existing, _)| existing.as_str() == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// A parsed JSON file
+pub struct JsonDocument {
+    pub source: NamedSource<String>,
+    pub root: SpannedJson,
+}
+
+impl JsonDocument {
+    pub fn from_path(path: &Path) -> miette::Result<Self> {
+        let text = std::fs::read_to_string(path).into_diagnostic()?;
+        Self::parse(&path.to_string_lossy(), text)
+    }
+
+    pub fn parse(name: &str, text: String) -> miette::Result<Self> {
+        let source = NamedSource::new(name, text.clone()).with_language("json");
+        let root = parse_at(&source, &text, 0)?;
+
+        Ok(JsonDocument { source, root })
+    }
+}
+
+/// Parses JSON written in a KDL string, with spans that point into the KDL. Spans are exact for
+/// raw strings and strings without escapes, and cover the whole string otherwise
+pub fn parse_embedded(
+    source: &NamedSource<String>,
+    string: &SpannedScalar<String>,
+) -> miette::Result<SpannedJson> {
+    let start = string.span.offset();
+    let literal = &source.inner()[start..start + string.span.len()];
+
+    let offset = match literal.strip_prefix('r') {
+        Some(raw) => {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            Some(start + hashes + 2)
+        }
+        None if literal.get(1..literal.len() - 1) == Some(string.as_str()) => Some(start + 1),
+        None => None,
+    };
+
+    match offset {
+        Some(offset) => Ok(parse_at(source, string, offset)?),
+        None => {
+            let mut json = parse_at(source, string, start).map_err(|_| ParseJsonError {
+                src: source.clone(),
+                span: string.span,
+                reason: "the JSON is invalid".to_string(),
+            })?;
+            json.respan(string.span);
+            Ok(json)
+        }
+    }
+}
+
+/// Parses `text`, which starts at `offset` in `source`
+fn parse_at(
+    source: &NamedSource<String>,
+    text: &str,
+    offset: usize,
+) -> Result<SpannedJson, ParseJsonError> {
+    document(offset)
+        .parse(text)
+        .into_result()
+        .map_err(|errors| {
+            let err = errors.first().expect("parsing failed with no errors");
+            ParseJsonError {
+                src: source.clone(),
+                span: (err.span().start + offset..err.span().end + offset).into(),
+                reason: err.reason().to_string(),
+            }
+        })
+}
+
+impl SpannedJson {
+    /// Replaces the span of this value and everything inside it
+    fn respan(&mut self, span: SourceSpan) {
+        self.span = span;
+
+        match &mut self.kind {
+            SpannedJsonKind::Array(items) => items.iter_mut().for_each(|item| item.respan(span)),
+            SpannedJsonKind::Object(members) => {
+                for (key, value) in members {
+                    key.span = span;
+                    value.respan(span);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Failed to parse JSON")]
+struct ParseJsonError {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    #[help]
+    reason: String,
+}
+
+fn document<'a>(span_offset: usize) -> impl Parser<'a, &'a str, SpannedJson, Err<Rich<'a, char>>> {
+    value(span_offset).then_ignore(end())
+}
+
+/// A value, with any whitespace around it. Spans are offset by `span_offset`
+fn value<'a>(
+    span_offset: usize,
+) -> impl Parser<'a, &'a str, SpannedJson, Err<Rich<'a, char>>> + Clone {
+    recursive(move |value| {
+        let digits = text::digits(10);
+        let fraction = just('.').then(digits);
+        let exponent = one_of("eE").then(one_of("+-").or_not()).then(digits);
+
+        let number = just('-')
+            .or_not()
+            .then(text::int(10))
+            .then(fraction.or_not())
+            .then(exponent.or_not())
+            .to_slice()
+            .map(|s: &str| SpannedJsonKind::Number(s.to_string()));
+
+        let array = just('[')
+            .ignore_then(value.clone().separated_by(just(',')).collect::<Vec<_>>())
+            .then_ignore(whitespace())
+            .then_ignore(just(']'))
+            .map(SpannedJsonKind::Array);
+
+        let key = string().map_with(move |value, extra| {
+            let span: SimpleSpan = extra.span();
+            SpannedScalar {
+                value,
+                span: (span.start + span_offset..span.end + span_offset).into(),
+            }
+        });
+
+        let member = whitespace()
+            .ignore_then(key)
+            .then_ignore(whitespace())
+            .then_ignore(just(':'))
+            .then(value);
+
+        let object = just('{')
+            .ignore_then(member.separated_by(just(',')).collect::<Vec<_>>())
+            .then_ignore(whitespace())
+            .then_ignore(just('}'))
+            .map(SpannedJsonKind::Object);
+
+        let kind = choice((
+            just("null").to(SpannedJsonKind::Null),
+            just("true").to(SpannedJsonKind::Bool(true)),
+            just("false").to(SpannedJsonKind::Bool(false)),
+            number,
+            string().map(SpannedJsonKind::String),
+            array,
+            object,
+        ))
+        .labelled("a value");
+
+        let spanned = kind.map_with(move |kind, extra| {
+            let span: SimpleSpan = extra.span();
+            SpannedJson {
+                span: (span.start + span_offset..span.end + span_offset).into(),
+                kind,
+            }
+        });
+
+        whitespace().ignore_then(spanned).then_ignore(whitespace())
+    })
+}
+
+/// A string literal. Escapes are decoded as UTF-16, so that surrogate pairs written as two
+/// `\u` escapes become a single character
+fn string<'a>() -> impl Parser<'a, &'a str, String, Err<Rich<'a, char>>> + Clone {
+    let utf16 = |c: char| c.encode_utf16(&mut [0; 2]).to_vec();
+
+    let hex = any()
+        .filter(|c: &char| c.is_ascii_hexdigit())
+        .repeated()
+        .exactly(4)
+        .to_slice()
+        .map(|s: &str| u16::from_str_radix(s, 16).expect("checked by the parser"));
+
+    let escape = just('\\').ignore_then(choice((
+        just('"').to('"').map(utf16),
+        just('\\').to('\\').map(utf16),
+        just('/').to('/').map(utf16),
+        just('b').to('\u{08}').map(utf16),
+        just('f').to('\u{0C}').map(utf16),
+        just('n').to('\n').map(utf16),
+        just('r').to('\r').map(utf16),
+        just('t').to('\t').map(utf16),
+        just('u').ignore_then(hex).map(|unit| vec![unit]),
+    )));
+
+    let unescaped = none_of("\"\\")
+        .try_map(|c: char, span| match c < ' ' {
+            true => Err(Rich::custom(span, "control characters have to be escaped")),
+            false => Ok(c),
+        })
+        .map(utf16);
+
+    unescaped
+        .or(escape)
+        .repeated()
+        .collect::<Vec<_>>()
+        .map(|units| String::from_utf16_lossy(&units.concat()))
+        .delimited_by(just('"'), just('"'))
+        .labelled("a string")
+}
+
+fn whitespace<'a>() -> impl Parser<'a, &'a str, (), Err<Rich<'a, char>>> + Clone {
+    one_of(" \t\r\n").labelled("whitespace").repeated()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(src: &str) -> SpannedJson {
+        JsonDocument::parse("test.json", src.to_string())
+            .unwrap()
+            .root
+    }
+
+    /// Drops the spans, to compare the structure of values
+    fn strip(json: &SpannedJson) -> String {
+        match &json.kind {
+            SpannedJsonKind::Null => "null".into(),
+            SpannedJsonKind::Bool(value) => value.to_string(),
+            SpannedJsonKind::Number(value) => value.clone(),
+            SpannedJsonKind::String(value) => format!("{value:?}"),
+            SpannedJsonKind::Array(items) => {
+                let items: Vec<_> = items.iter().map(strip).collect();
+                format!("[{}]", items.join(","))
+            }
+            SpannedJsonKind::Object(members) => {
+                let members: Vec<_> = members
+                    .iter()
+                    .map(|(key, value)| format!("{:?}:{}", key.value, strip(value)))
+                    .collect();
+                format!("{{{}}}", members.join(","))
+            }
+        }
+    }
+
+    #[test]
+    fn can_parse_json() {
+        assert_eq!(strip(&parse("null")), "null");
+        assert_eq!(strip(&parse(" true ")), "true");
+        assert_eq!(strip(&parse("-12.5e+3")), "-12.5e+3");
+        assert_eq!(strip(&parse("[]")), "[]");
+        assert_eq!(strip(&parse("[ ]")), "[]");
+        assert_eq!(strip(&parse("{ }")), "{}");
+        assert_eq!(
+            strip(&parse(r#" { "a" : [1, 2.0, "x"], "b": {"c": null} } "#)),
+            r#"{"a":[1,2.0,"x"],"b":{"c":null}}"#
+        );
+    }
+
+    #[test]
+    fn can_parse_escapes() {
+        assert_eq!(
+            strip(&parse(r#""a\"b\\c\/d\né""#)),
+            format!("{:?}", "a\"b\\c/d\né")
+        );
+        assert_eq!(strip(&parse(r#""🦀""#)), format!("{:?}", "🦀"));
+        assert_eq!(strip(&parse(r#""\ud83e\udd80""#)), format!("{:?}", "🦀"));
+    }
+
+    #[test]
+    fn keeps_spans() {
+        let json = parse(r#"{"key": [1, true]}"#);
+        assert_eq!(json.span, (0..18).into());
+
+        let SpannedJsonKind::Object(members) = &json.kind else {
+            panic!("expected an object");
+        };
+        let (key, value) = &members[0];
+        assert_eq!(key.span, (1..6).into());
+        assert_eq!(value.span, (8..17).into());
+
+        let SpannedJsonKind::Array(items) = &value.kind else {
+            panic!("expected an array");
+        };
+        assert_eq!(items[1].span, (12..16).into());
+    }
+
+    #[test]
+    fn rejects_invalid_json() {
+        for src in [
+            "",
+            "[1, 2,]",
+            "{\"a\" 1}",
+            "01",
+            "\"unterminated",
+            "\"raw\ttab\"",
+            "{} {}",
+            "'single'",
+        ] {
+            assert!(
+                JsonDocument::parse("test.json", src.to_string()).is_err(),
+                "{src:?} should not parse"
+            );
+        }
+    }
+}
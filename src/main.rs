@@ -5,6 +5,7 @@ use crate::args::{Args, run};
 mod args;
 mod codegen;
 mod context;
+mod json;
 mod model;
 mod validate;
 
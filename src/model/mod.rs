@@ -31,6 +31,11 @@ pub struct Library {
     #[knus(child)]
     pub defaults: Option<Defaults>,
 
+    /// Types defined outside of the generated file (for example, in another package), along with
+    /// the information needed to use them in fields
+    #[knus(children(name = "external-type"))]
+    pub external_types: Vec<ExternalType>,
+
     #[knus(children(name = "enum"))]
     pub enums: Vec<Enum>,
 
@@ -76,6 +81,10 @@ pub struct Class {
     pub fields: Vec<Field>,
     #[knus(child, unwrap(argument))]
     pub docs: Option<SpannedScalar<String>>,
+    /// Example JSON documents, which are checked during validation and included in the doc
+    /// comment
+    #[knus(children(name = "example"), unwrap(argument))]
+    pub examples: Vec<SpannedScalar<String>>,
     #[knus(child, unwrap(argument))]
     pub json_key_case: Option<SpannedScalar<RenameCase>>,
     #[knus(child, unwrap(argument))]
@@ -93,6 +102,56 @@ pub struct Class {
     /// Extra text to include into the class body
     #[knus(child, unwrap(argument))]
     pub builder_extra_dart: Option<SpannedScalar<String>>,
+
+    #[knus(child, unwrap(argument))]
+    pub modifier: Option<SpannedScalar<ClassModifier>>,
+    /// A superclass for this class. Not allowed for classes in a union, since they already extend
+    /// the union
+    #[knus(child, unwrap(argument))]
+    pub extends: Option<SpannedScalar<String>>,
+    #[knus(children(name = "with"), unwrap(argument))]
+    pub with: Vec<SpannedScalar<String>>,
+    #[knus(children(name = "implements"), unwrap(argument))]
+    pub implements: Vec<SpannedScalar<String>>,
+
+    #[knus(child, unwrap(argument))]
+    pub builder_modifier: Option<SpannedScalar<ClassModifier>>,
+    #[knus(children(name = "builder-with"), unwrap(argument))]
+    pub builder_with: Vec<SpannedScalar<String>>,
+    #[knus(children(name = "builder-implements"), unwrap(argument))]
+    pub builder_implements: Vec<SpannedScalar<String>>,
+    #[knus(child, unwrap(argument))]
+    pub builder_mode: Option<SpannedScalar<BuilderMode>>,
+
+    /// Whether `fromJson` should reject values that violate field constraints
+    #[knus(child, unwrap(argument))]
+    pub validate_from_json: Option<SpannedScalar<bool>>,
+
+    /// The fields to sort by, which makes the class `Comparable`
+    #[knus(child)]
+    pub ordering: Option<Ordering>,
+}
+
+/// The fields that `compareTo` compares, in priority order
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+#[knus(span_type = Span)]
+pub struct Ordering {
+    #[knus(unwrap(span))]
+    pub span: Span,
+    #[knus(children(name = "field"))]
+    pub fields: Vec<OrderingField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+#[knus(span_type = Span)]
+pub struct OrderingField {
+    #[knus(argument)]
+    pub name: SpannedScalar<String>,
+    #[knus(property)]
+    pub order: Option<SpannedScalar<SortOrder>>,
+    /// Where nulls go, for nullable fields. Independent of `order`
+    #[knus(property)]
+    pub order_nulls: Option<SpannedScalar<NullsOrder>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Decode)]
@@ -103,8 +162,8 @@ pub struct Field {
     #[knus(property(name = "type"))]
     pub ty: SpannedScalar<String>,
 
-    #[knus(child, unwrap(argument))]
-    pub defaults_to: Option<Value<Span>>,
+    #[knus(child)]
+    pub defaults_to: Option<DefaultValue>,
 
     #[knus(child, unwrap(argument))]
     pub defaults_to_dart: Option<SpannedScalar<String>>,
@@ -119,6 +178,76 @@ pub struct Field {
 
     #[knus(child, unwrap(argument))]
     pub json_key: Option<SpannedScalar<String>>,
+    /// How records in the type of this field are written in JSON
+    #[knus(child, unwrap(argument))]
+    pub json_record_representation: Option<SpannedScalar<RecordRepresentation>>,
+
+    /// Whether this field is compared in `operator==` and included in `hashCode`
+    #[knus(child, unwrap(argument))]
+    pub equality: Option<SpannedScalar<bool>>,
+    /// How this field appears in the generated `toString`
+    #[knus(child, unwrap(argument))]
+    pub to_string: Option<SpannedScalar<ToStringMode>>,
+
+    /// The smallest allowed value of a numeric field
+    #[knus(child, unwrap(argument))]
+    pub min: Option<Value<Span>>,
+    /// The largest allowed value of a numeric field
+    #[knus(child, unwrap(argument))]
+    pub max: Option<Value<Span>>,
+    /// A regular expression that a `String` field must match
+    #[knus(child, unwrap(argument))]
+    pub pattern: Option<SpannedScalar<String>>,
+    #[knus(child, unwrap(argument))]
+    pub min_length: Option<SpannedScalar<u64>>,
+    #[knus(child, unwrap(argument))]
+    pub max_length: Option<SpannedScalar<u64>>,
+    #[knus(child, unwrap(argument))]
+    pub min_items: Option<SpannedScalar<u64>>,
+    #[knus(child, unwrap(argument))]
+    pub max_items: Option<SpannedScalar<u64>>,
+}
+
+/// The value of `defaults-to`, which is either a KDL scalar (`defaults-to 123`) or a structured
+/// value made of `item`, `entry` and `field` children
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+#[knus(span_type = Span)]
+pub struct DefaultValue {
+    #[knus(unwrap(span))]
+    pub span: Span,
+    /// The scalar value, if any. Only one is allowed, which is checked during validation
+    #[knus(arguments)]
+    pub values: Vec<Value<Span>>,
+
+    /// The elements of a `List` or `Set`
+    #[knus(children(name = "item"))]
+    pub items: Vec<DefaultValue>,
+    /// The entries of a `Map`
+    #[knus(children(name = "entry"))]
+    pub entries: Vec<DefaultEntry>,
+    /// The fields of a generated class
+    #[knus(children(name = "field"))]
+    pub fields: Vec<DefaultEntry>,
+}
+
+/// A map entry (`entry "key" 123`) or class field (`field "name" 123`) in a structured default.
+/// Like [`DefaultValue`], the value can also be given as children
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+#[knus(span_type = Span)]
+pub struct DefaultEntry {
+    #[knus(unwrap(span))]
+    pub span: Span,
+    #[knus(argument)]
+    pub key: Value<Span>,
+    #[knus(arguments)]
+    pub values: Vec<Value<Span>>,
+
+    #[knus(children(name = "item"))]
+    pub items: Vec<DefaultValue>,
+    #[knus(children(name = "entry"))]
+    pub entries: Vec<DefaultEntry>,
+    #[knus(children(name = "field"))]
+    pub fields: Vec<DefaultEntry>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Decode)]
@@ -135,13 +264,46 @@ pub struct Union {
     #[knus(child, unwrap(argument))]
     pub json_discriminant_value_case: Option<SpannedScalar<RenameCase>>,
     #[knus(child, unwrap(argument))]
+    pub json_representation: Option<SpannedScalar<JsonRepresentation>>,
+    #[knus(child, unwrap(argument))]
+    pub json_content_key: Option<SpannedScalar<String>>,
+    #[knus(child, unwrap(argument))]
     pub annotations: Option<SpannedScalar<String>>,
     #[knus(child, unwrap(argument))]
     pub builder_annotations: Option<SpannedScalar<String>>,
     #[knus(child, unwrap(argument))]
     pub docs: Option<SpannedScalar<String>>,
+    /// Example JSON documents, which are checked during validation and included in the doc
+    /// comment
+    #[knus(children(name = "example"), unwrap(argument))]
+    pub examples: Vec<SpannedScalar<String>>,
+    #[knus(child, unwrap(argument))]
+    pub modifier: Option<SpannedScalar<ClassModifier>>,
+    #[knus(children(name = "with"), unwrap(argument))]
+    pub with: Vec<SpannedScalar<String>>,
+    #[knus(children(name = "implements"), unwrap(argument))]
+    pub implements: Vec<SpannedScalar<String>>,
+
+    #[knus(child, unwrap(argument))]
+    pub builder_modifier: Option<SpannedScalar<ClassModifier>>,
+    #[knus(children(name = "builder-with"), unwrap(argument))]
+    pub builder_with: Vec<SpannedScalar<String>>,
+    #[knus(children(name = "builder-implements"), unwrap(argument))]
+    pub builder_implements: Vec<SpannedScalar<String>>,
+
+    /// Generate `when`, `maybeWhen` and `mapOrNull` methods that take a callback for each member
+    #[knus(child, unwrap(argument))]
+    pub generate_when: Option<SpannedScalar<bool>>,
+
+    /// The discriminant value used by the enclosing union, if this union is nested inside another
+    #[knus(child, unwrap(argument))]
+    pub json_discriminant_value: Option<Value<Span>>,
+
     #[knus(children(name = "class"))]
     pub classes: Vec<Class>,
+    /// Unions nested inside this union, which extend this union
+    #[knus(children(name = "union"))]
+    pub unions: Vec<Union>,
     #[knus(children, unwrap(argument))]
     pub extra_dart: Vec<SpannedScalar<String>>,
 }
@@ -155,10 +317,17 @@ pub struct Enum {
     pub name: SpannedScalar<String>,
     #[knus(child, unwrap(argument))]
     pub docs: Option<SpannedScalar<String>>,
+    /// Example JSON documents, which are checked during validation and included in the doc
+    /// comment
+    #[knus(children(name = "example"), unwrap(argument))]
+    pub examples: Vec<SpannedScalar<String>>,
     #[knus(child, unwrap(argument))]
     pub annotations: Option<SpannedScalar<String>>,
     #[knus(child, unwrap(argument))]
     pub extra_dart: Option<SpannedScalar<String>>,
+    /// Implement `Comparable`, ordering variants by their declaration
+    #[knus(child, unwrap(argument))]
+    pub comparable: Option<SpannedScalar<bool>>,
     #[knus(children(name = "variant"))]
     pub variants: Vec<EnumVariant>,
 }
@@ -175,3 +344,26 @@ pub struct EnumVariant {
     #[knus(child, unwrap(argument))]
     pub json_value: Option<Value<Span>>,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Decode)]
+#[knus(span_type = Span)]
+pub struct ExternalType {
+    #[knus(unwrap(span))]
+    pub span: Span,
+    #[knus(argument)]
+    pub name: SpannedScalar<String>,
+
+    /// Imports required to use this type. These are added to the top of the generated file
+    #[knus(children(name = "import"), unwrap(argument))]
+    pub imports: Vec<SpannedScalar<String>>,
+
+    /// Used to convert this type to JSON wherever it appears, including inside collections
+    #[knus(child, unwrap(argument))]
+    pub to_json: Option<SpannedScalar<String>>,
+    /// Used to convert this type from JSON wherever it appears, including inside collections
+    #[knus(child, unwrap(argument))]
+    pub from_json: Option<SpannedScalar<String>>,
+
+    #[knus(child, unwrap(argument))]
+    pub equality: Option<SpannedScalar<Equality>>,
+}
@@ -13,6 +13,11 @@ pub struct Defaults {
     #[knus(child, unwrap(argument))]
     pub dart_format_language_version: Option<SpannedScalar<String>>,
 
+    /// Types which are defined outside of the config (e.g. imported in the preamble), and so
+    /// should not be reported as unknown
+    #[knus(children(name = "allowed-type"), unwrap(argument))]
+    pub allowed_types: Vec<SpannedScalar<String>>,
+
     #[knus(child)]
     pub class: Option<ClassOptions>,
 
@@ -32,6 +37,16 @@ pub struct ClassOptions {
     pub annotations: Option<SpannedScalar<String>>,
     #[knus(child, unwrap(argument))]
     pub builder_annotations: Option<SpannedScalar<String>>,
+
+    #[knus(child, unwrap(argument))]
+    pub modifier: Option<SpannedScalar<ClassModifier>>,
+    #[knus(child, unwrap(argument))]
+    pub builder_modifier: Option<SpannedScalar<ClassModifier>>,
+    #[knus(child, unwrap(argument))]
+    pub builder_mode: Option<SpannedScalar<BuilderMode>>,
+
+    #[knus(child, unwrap(argument))]
+    pub validate_from_json: Option<SpannedScalar<bool>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Decode)]
@@ -47,17 +62,35 @@ pub struct UnionOptions {
 
     #[knus(child, unwrap(argument))]
     pub json_discriminant: Option<SpannedScalar<String>>,
+    #[knus(child, unwrap(argument))]
+    pub json_representation: Option<SpannedScalar<JsonRepresentation>>,
+    #[knus(child, unwrap(argument))]
+    pub json_content_key: Option<SpannedScalar<String>>,
+
+    #[knus(child, unwrap(argument))]
+    pub generate_when: Option<SpannedScalar<bool>>,
 
     #[knus(child, unwrap(argument))]
     pub annotations: Option<SpannedScalar<String>>,
     #[knus(child, unwrap(argument))]
     pub builder_annotations: Option<SpannedScalar<String>>,
+
+    #[knus(child, unwrap(argument))]
+    pub modifier: Option<SpannedScalar<ClassModifier>>,
+    #[knus(child, unwrap(argument))]
+    pub builder_modifier: Option<SpannedScalar<ClassModifier>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Decode)]
 pub struct FieldOptions {
     #[knus(child, unwrap(argument))]
     pub json_key_case: Option<SpannedScalar<RenameCase>>,
+    #[knus(child, unwrap(argument))]
+    pub json_record_representation: Option<SpannedScalar<RecordRepresentation>>,
+    #[knus(child, unwrap(argument))]
+    pub equality: Option<SpannedScalar<bool>>,
+    #[knus(child, unwrap(argument))]
+    pub to_string: Option<SpannedScalar<ToStringMode>>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
@@ -69,6 +102,101 @@ pub enum RenameCase {
     ScreamingSnake,
 }
 
+/// The Dart class modifier to put in front of a generated class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum ClassModifier {
+    Final,
+    Base,
+    Interface,
+    None,
+}
+
+impl ClassModifier {
+    /// The modifier keyword, including a trailing space if it is non-empty
+    pub fn prefix(self) -> &'static str {
+        match self {
+            ClassModifier::Final => "final ",
+            ClassModifier::Base => "base ",
+            ClassModifier::Interface => "interface ",
+            ClassModifier::None => "",
+        }
+    }
+
+    /// Whether Dart requires subclasses of a class with this modifier to be `base`, `final` or
+    /// `sealed`
+    pub fn restricts_subclasses(self) -> bool {
+        matches!(self, ClassModifier::Final | ClassModifier::Base)
+    }
+}
+
+/// Which fields a builder needs before it can be created
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum BuilderMode {
+    /// Every field is required by the builder's constructor
+    Strict,
+    /// Fields without a default are nullable until they are set, and `build` throws if any of
+    /// them are still missing
+    Partial,
+}
+
+/// How a union's members identify themselves in JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum JsonRepresentation {
+    /// `{"type": "dog", "name": "Rex"}`
+    Internal,
+    /// `{"type": "dog", "data": {"name": "Rex"}}`
+    Adjacent,
+    /// `{"dog": {"name": "Rex"}}`
+    External,
+    /// `{"name": "Rex"}`, where the member is decided by which keys are present
+    Untagged,
+}
+
+/// How record types are written in JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum RecordRepresentation {
+    /// `[1.5, 2.5]`. Only records without named fields can be written this way
+    Array,
+    /// `{"$1": 1.5, "$2": 2.5}`, or `{"x": 1.5, "y": 2.5}` for named fields
+    Object,
+}
+
+/// How a field appears in the generated `toString`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum ToStringMode {
+    /// `name: value`
+    Show,
+    /// `name: <redacted>`, for values that shouldn't end up in logs
+    Redacted,
+    /// Left out entirely
+    Omit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Where nulls are sorted, relative to every other value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// How values of a type should be compared in generated `operator==` and `hashCode`
+/// implementations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, DecodeScalar)]
+pub enum Equality {
+    /// Use the type's own `operator==` and `hashCode`
+    Shallow,
+    /// Use `DeepCollectionEquality` from `package:collection`
+    Deep,
+    /// Use `identical` and `identityHashCode`
+    Identity,
+}
+
 impl From<RenameCase> for Case<'_> {
     fn from(value: RenameCase) -> Self {
         match value {
@@ -13,3 +13,39 @@ macro_rules! parse_snapshot {
 }
 
 crate::all_test_files!(parse_snapshot);
+
+#[test]
+fn external_imports_skip_the_preamble() {
+    let text = r#"
+        preamble "import 'package:collection/collection.dart';"
+        external-type "Tags" {
+            import "package:tags/tags.dart"
+            equality "deep"
+        }
+    "#;
+    let config = Library::parse_impl(None, text).unwrap();
+
+    assert_eq!(config.external_imports(), ["package:tags/tags.dart"]);
+
+    // only whole, unprefixed directives count
+    let text = r#"
+        preamble r"
+            // import 'package:tags/tags.dart';
+            import 'package:collection/collection.dart' as collection;
+            import 'package:tags/tags.dart.old';
+        "
+        external-type "Tags" {
+            import "package:tags/tags.dart"
+            equality "deep"
+        }
+    "#;
+    let config = Library::parse_impl(None, text).unwrap();
+
+    assert_eq!(
+        config.external_imports(),
+        [
+            "package:collection/collection.dart",
+            "package:tags/tags.dart"
+        ]
+    );
+}
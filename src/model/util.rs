@@ -7,7 +7,10 @@ use std::{
 use convert_case::Casing;
 use knus::{DecodeScalar, ast::Value, span::Span, traits::ErrorSpan};
 
-use crate::codegen::format_dart_literal_const;
+use crate::{
+    codegen::{dart_string_literal, format_dart_literal_const},
+    json::Json,
+};
 
 use super::*;
 
@@ -57,12 +60,64 @@ impl Library {
     pub fn all_classes(&self) -> impl Iterator<Item = &Class> {
         self.classes
             .iter()
-            .chain(self.unions.iter().flat_map(|union| &union.classes))
+            .chain(self.all_unions().flat_map(|union| &union.classes))
+    }
+
+    /// All unions, including unions nested inside other unions
+    pub fn all_unions(&self) -> impl Iterator<Item = &Union> {
+        fn collect<'lib>(unions: &'lib [Union], out: &mut Vec<&'lib Union>) {
+            for union in unions {
+                out.push(union);
+                collect(&union.unions, out);
+            }
+        }
+
+        let mut unions = vec![];
+        collect(&self.unions, &mut unions);
+        unions.into_iter()
+    }
+
+    /// The union that directly contains `union`, if it is nested
+    pub fn parent_union(&self, union: &Union) -> Option<&Union> {
+        self.all_unions().find(|parent| {
+            parent
+                .unions
+                .iter()
+                .any(|nested| std::ptr::eq(nested, union))
+        })
+    }
+
+    /// The classes in `union`, including the classes in unions nested inside it
+    pub fn union_members<'lib>(&'lib self, union: &'lib Union) -> Vec<&'lib Class> {
+        let mut members: Vec<_> = union.classes.iter().collect();
+        for nested in &union.unions {
+            members.extend(self.union_members(nested));
+        }
+        members
+    }
+
+    /// The union that `class` is a member of, if any
+    pub fn union_of(&self, class: &Class) -> Option<&Union> {
+        self.all_unions().find(|union| {
+            union
+                .classes
+                .iter()
+                .any(|member| std::ptr::eq(member, class))
+        })
+    }
+
+    /// `union` and all the unions that contain it, starting with the outermost
+    pub fn union_ancestors<'lib>(&'lib self, union: &'lib Union) -> Vec<&'lib Union> {
+        let mut ancestors = vec![union];
+        while let Some(parent) = self.parent_union(ancestors[0]) {
+            ancestors.insert(0, parent);
+        }
+        ancestors
     }
 
     pub fn class_and_union_names(&self) -> impl Iterator<Item = &SpannedScalar<String>> {
         let class_names = self.all_classes().map(|class| &class.name);
-        let union_names = self.unions.iter().map(|union| &union.name);
+        let union_names = self.all_unions().map(|union| &union.name);
 
         class_names.chain(union_names)
     }
@@ -77,8 +132,27 @@ impl Library {
         self.all_classes().flat_map(|class| &class.fields)
     }
 
+    /// All KDL values used in defaults, including values nested inside structured defaults
     pub fn all_raw_values(&self) -> impl Iterator<Item = &Value<Span>> {
-        self.all_fields().flat_map(|field| &field.defaults_to)
+        fn collect<'lib>(default: &'lib DefaultValue, out: &mut Vec<&'lib Value<Span>>) {
+            out.extend(&default.values);
+            for item in &default.items {
+                collect(item, out);
+            }
+            for entry in default.entries.iter().chain(&default.fields) {
+                out.push(&entry.key);
+                out.extend(&entry.values);
+                for item in &entry.items {
+                    collect(item, out);
+                }
+            }
+        }
+
+        let mut values = vec![];
+        for default in self.all_fields().flat_map(|field| &field.defaults_to) {
+            collect(default, &mut values);
+        }
+        values.into_iter()
     }
 
     pub fn type_has_builder(&self, type_name: &str) -> bool {
@@ -86,6 +160,120 @@ impl Library {
             .any(|name| name.as_str() == type_name)
     }
 
+    pub fn external_type(&self, type_name: &str) -> Option<&ExternalType> {
+        self.external_types
+            .iter()
+            .find(|external| external.name.as_str() == type_name)
+    }
+
+    pub fn equality_for(&self, type_name: &str) -> Equality {
+        self.external_type(type_name)
+            .and_then(|external| external.equality.as_ref())
+            .map(|equality| equality.value)
+            .unwrap_or(Equality::Shallow)
+    }
+
+    /// The imports needed by all external types, in the order they are first declared. Imports
+    /// that the preamble already has are left out, so that the analyzer doesn't warn about
+    /// duplicate imports
+    pub fn external_imports(&self) -> Vec<&str> {
+        let mut imports = vec![];
+
+        let needs_collection = self
+            .external_types
+            .iter()
+            .any(|external| self.equality_for(&external.name) == Equality::Deep);
+
+        if needs_collection {
+            imports.push("package:collection/collection.dart");
+        }
+
+        for import in self
+            .external_types
+            .iter()
+            .flat_map(|external| &external.imports)
+        {
+            if !imports.contains(&import.as_str()) {
+                imports.push(import.as_str());
+            }
+        }
+
+        let preamble = plain_imports(self.preamble.as_deref().unwrap_or_default());
+        imports.retain(|import| !preamble.contains(import));
+
+        imports
+    }
+
+    /// Whether the preamble makes the generated file a part of another library, in which case
+    /// it can't have imports of its own
+    pub fn is_part_file(&self) -> bool {
+        self.preamble
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .any(|line| line.trim().starts_with("part of "))
+    }
+
+    pub fn class_modifier<'lib>(
+        &'lib self,
+        class: &'lib Class,
+    ) -> Option<&'lib SpannedScalar<ClassModifier>> {
+        class.modifier.as_ref().or_else(|| {
+            self.defaults
+                .as_ref()
+                .and_then(|d| d.class.as_ref()?.modifier.as_ref())
+        })
+    }
+
+    pub fn class_builder_modifier<'lib>(
+        &'lib self,
+        class: &'lib Class,
+    ) -> Option<&'lib SpannedScalar<ClassModifier>> {
+        class.builder_modifier.as_ref().or_else(|| {
+            self.defaults
+                .as_ref()
+                .and_then(|d| d.class.as_ref()?.builder_modifier.as_ref())
+        })
+    }
+
+    pub fn builder_mode_for(&self, class: &Class) -> BuilderMode {
+        class
+            .builder_mode
+            .as_ref()
+            .or_else(|| {
+                self.defaults
+                    .as_ref()?
+                    .class
+                    .as_ref()?
+                    .builder_mode
+                    .as_ref()
+            })
+            .map(|mode| mode.value)
+            .unwrap_or(BuilderMode::Strict)
+    }
+
+    pub fn union_modifier<'lib>(
+        &'lib self,
+        union: &'lib Union,
+    ) -> Option<&'lib SpannedScalar<ClassModifier>> {
+        union.modifier.as_ref().or_else(|| {
+            self.defaults
+                .as_ref()
+                .and_then(|d| d.union.as_ref()?.modifier.as_ref())
+        })
+    }
+
+    pub fn union_builder_modifier<'lib>(
+        &'lib self,
+        union: &'lib Union,
+    ) -> Option<&'lib SpannedScalar<ClassModifier>> {
+        union.builder_modifier.as_ref().or_else(|| {
+            self.defaults
+                .as_ref()
+                .and_then(|d| d.union.as_ref()?.builder_modifier.as_ref())
+        })
+    }
+
     pub fn json_key_for<'lib>(&self, class: &'lib Class, field: &'lib Field) -> Cow<'lib, str> {
         if let Some(key) = &field.json_key {
             return Cow::Borrowed(key);
@@ -104,20 +292,241 @@ impl Library {
         }
     }
 
+    pub fn record_representation_for(&self, field: &Field) -> RecordRepresentation {
+        field
+            .json_record_representation
+            .as_ref()
+            .or_else(|| {
+                self.defaults
+                    .as_ref()
+                    .and_then(|d| d.field.as_ref()?.json_record_representation.as_ref())
+            })
+            .map(|representation| representation.value)
+            .unwrap_or(RecordRepresentation::Array)
+    }
+
+    /// Whether `field` is compared in `operator==` and included in `hashCode`
+    pub fn compares_field(&self, field: &Field) -> bool {
+        field
+            .equality
+            .as_ref()
+            .or_else(|| self.defaults.as_ref()?.field.as_ref()?.equality.as_ref())
+            .is_none_or(|equality| equality.value)
+    }
+
+    pub fn to_string_mode_for(&self, field: &Field) -> ToStringMode {
+        field
+            .to_string
+            .as_ref()
+            .or_else(|| self.defaults.as_ref()?.field.as_ref()?.to_string.as_ref())
+            .map(|mode| mode.value)
+            .unwrap_or(ToStringMode::Show)
+    }
+
     pub fn discriminant_value_for<'lib>(
         &'lib self,
         union: &'lib Union,
         class: &'lib Class,
     ) -> Cow<'lib, str> {
-        if let Some(value) = &class.json_discriminant_value {
-            return Cow::Owned(format_dart_literal_const(value));
+        self.discriminant_value(union, &class.name, class.json_discriminant_value.as_ref())
+    }
+
+    /// The same value as [`Library::discriminant_value_for`], as JSON
+    pub fn discriminant_json_value_for(&self, union: &Union, class: &Class) -> Json {
+        self.discriminant_json(union, &class.name, class.json_discriminant_value.as_ref())
+    }
+
+    /// The discriminant value used by `union` to identify the nested union `nested`
+    pub fn nested_discriminant_value_for<'lib>(
+        &'lib self,
+        union: &'lib Union,
+        nested: &'lib Union,
+    ) -> Cow<'lib, str> {
+        self.discriminant_value(union, &nested.name, nested.json_discriminant_value.as_ref())
+    }
+
+    fn discriminant_value<'lib>(
+        &'lib self,
+        union: &'lib Union,
+        name: &str,
+        explicit: Option<&Value<Span>>,
+    ) -> Cow<'lib, str> {
+        match explicit {
+            Some(value) => Cow::Owned(format_dart_literal_const(value)),
+            None => Cow::Owned(dart_string_literal(&self.discriminant_name(union, name))),
+        }
+    }
+
+    /// The same value as [`Library::discriminant_value`], as JSON rather than a Dart literal
+    fn discriminant_json(&self, union: &Union, name: &str, explicit: Option<&Value<Span>>) -> Json {
+        match explicit {
+            Some(value) => Json::from_literal(value),
+            None => Json::String(self.discriminant_name(union, name)),
+        }
+    }
+
+    /// The name of a class or nested union, in the case that `union` uses for discriminants
+    fn discriminant_name(&self, union: &Union, name: &str) -> String {
+        match self.discriminant_value_case_for(union) {
+            Some(case) => name.to_case(case.into()),
+            None => name.to_string(),
+        }
+    }
+
+    /// Nested unions use the case of their parent, unless they specify their own
+    fn discriminant_value_case_for(&self, union: &Union) -> Option<RenameCase> {
+        match &union.json_discriminant_value_case {
+            Some(case) => Some(case.value),
+            None => self
+                .parent_union(union)
+                .and_then(|parent| self.discriminant_value_case_for(parent)),
         }
+    }
+
+    /// The values that `union`'s `fromJson` switches over, along with the types they dispatch to.
+    ///
+    /// Nested unions that share their parent's discriminant key are flattened into the parent,
+    /// so the parent dispatches directly to their classes. Nested unions with their own key are
+    /// dispatched to as a whole, and then switch on their own key.
+    pub fn discriminant_entries<'lib>(
+        &'lib self,
+        union: &'lib Union,
+    ) -> Vec<DiscriminantEntry<'lib>> {
+        let key = self.discriminant_key_for(union);
+
+        let mut entries: Vec<_> = union
+            .classes
+            .iter()
+            .map(|class| DiscriminantEntry {
+                value: self.discriminant_value_for(union, class),
+                json: self.discriminant_json_value_for(union, class),
+                name: &class.name,
+                span: discriminant_span(&class.name, class.json_discriminant_value.as_ref()),
+            })
+            .collect();
 
-        let name = match union.json_discriminant_value_case {
-            Some(case) => class.name.to_case(case.value.into()),
-            None => class.name.to_string(),
-        };
+        for nested in &union.unions {
+            if self.discriminant_key_for(nested) == key {
+                entries.extend(self.discriminant_entries(nested));
+            } else {
+                let explicit = nested.json_discriminant_value.as_ref();
+                entries.push(DiscriminantEntry {
+                    value: self.nested_discriminant_value_for(union, nested),
+                    json: self.discriminant_json(union, &nested.name, explicit),
+                    name: &nested.name,
+                    span: discriminant_span(&nested.name, explicit),
+                });
+            }
+        }
 
-        Cow::Owned(format!("\"{name}\""))
+        entries
     }
+
+    /// The discriminant keys and values that `class` writes in `toJson`, starting with the
+    /// outermost union
+    pub fn discriminants_for<'lib>(
+        &'lib self,
+        union: &'lib Union,
+        class: &'lib Class,
+    ) -> Vec<(&'lib str, Cow<'lib, str>)> {
+        self.discriminants_with(union, class, |union, name, explicit| {
+            self.discriminant_value(union, name, explicit)
+        })
+    }
+
+    /// The same discriminants as [`Library::discriminants_for`], as JSON
+    pub fn discriminants_json_for<'lib>(
+        &'lib self,
+        union: &'lib Union,
+        class: &'lib Class,
+    ) -> Vec<(&'lib str, Json)> {
+        self.discriminants_with(union, class, |union, name, explicit| {
+            self.discriminant_json(union, name, explicit)
+        })
+    }
+
+    fn discriminants_with<'lib, T>(
+        &'lib self,
+        union: &'lib Union,
+        class: &'lib Class,
+        value: impl Fn(&'lib Union, &'lib str, Option<&'lib Value<Span>>) -> T,
+    ) -> Vec<(&'lib str, T)> {
+        let ancestors = self.union_ancestors(union);
+
+        let mut discriminants = vec![];
+        for pair in ancestors.windows(2) {
+            let [parent, nested] = [pair[0], pair[1]];
+            let key = self.discriminant_key_for(parent);
+
+            if key != self.discriminant_key_for(nested) {
+                let explicit = nested.json_discriminant_value.as_ref();
+                discriminants.push((key, value(parent, &nested.name, explicit)));
+            }
+        }
+
+        let key = self.discriminant_key_for(union);
+        let explicit = class.json_discriminant_value.as_ref();
+        discriminants.push((key, value(union, &class.name, explicit)));
+
+        discriminants
+    }
+}
+
+impl DefaultValue {
+    /// The scalar value, if this isn't a structured value
+    pub fn value(&self) -> Option<&Value<Span>> {
+        self.values.first()
+    }
+}
+
+impl DefaultEntry {
+    /// The value of this entry, as if it were written as its own `defaults-to`
+    pub fn to_default_value(&self) -> DefaultValue {
+        DefaultValue {
+            span: self.span,
+            values: self.values.clone(),
+            items: self.items.clone(),
+            entries: self.entries.clone(),
+            fields: self.fields.clone(),
+        }
+    }
+}
+
+/// A single case in the `switch` of a union's `fromJson`
+pub struct DiscriminantEntry<'lib> {
+    /// The Dart literal that is matched against
+    pub value: Cow<'lib, str>,
+    /// The same value, as JSON
+    pub json: Json,
+    /// The name of the type that is decoded when the value matches
+    pub name: &'lib SpannedScalar<String>,
+    /// The explicit `json-discriminant-value` if there is one, otherwise the name
+    pub span: miette::SourceSpan,
+}
+
+fn discriminant_span(
+    name: &SpannedScalar<String>,
+    explicit: Option<&Value<Span>>,
+) -> miette::SourceSpan {
+    match explicit {
+        Some(value) => (*value.literal.span()).into(),
+        None => name.span,
+    }
+}
+
+/// The URIs of the `import` directives in a snippet of Dart which import everything without a
+/// prefix, such as `import 'package:x/x.dart';`. Directives are expected to be on one line each
+fn plain_imports(dart: &str) -> Vec<&str> {
+    dart.lines()
+        .filter_map(|line| line.trim().strip_prefix("import "))
+        .filter_map(|directive| {
+            let directive = directive.trim_start();
+            let quote = directive
+                .chars()
+                .next()
+                .filter(|c| matches!(c, '\'' | '"'))?;
+            let (uri, rest) = directive[1..].split_once(quote)?;
+            (rest.trim() == ";").then_some(uri)
+        })
+        .collect()
 }
@@ -152,6 +152,10 @@ fn check_builder(buf: &mut String, type_name: &str) -> std::fmt::Result {
         if (obj != rebuilt) {
             throw Exception("builder-roundrip error");
         }
+
+        if (obj != obj.rebuild((b) => b.update((_) {}))) {
+            throw Exception("rebuild error");
+        }
     "#;
 
     writeln!(buf, "void checkBuilder{type_name}({type_name} obj) {{")?;
@@ -217,21 +221,20 @@ environment:
     sdk: ">3.0.0"
 "#;
 
-#[test]
-fn integration_test() {
+/// Writes `files` into a new Dart package, then analyzes it and runs `dart` with `args`
+fn run_dart_package(pubspec: &str, files: &[(&str, String)], args: &[&str]) {
     let tempdir = tempdir::TempDir::new("dart-typegen-test").unwrap();
     let package_dir = tempdir.path().join("dart_typegen_test");
     std::fs::create_dir(&package_dir).unwrap();
 
     println!("package_dir: {}", package_dir.to_string_lossy());
 
-    let context = Context::from_str(include_str!(crate::test_file!(kitchen_sink))).unwrap();
-    let generated = context.codegen_to_string().unwrap();
-    let main = dart_format(main_dart(), Some("3.8")).unwrap();
-
-    std::fs::write(package_dir.join("pubspec.yaml"), PUBSPEC).unwrap();
-    std::fs::write(package_dir.join("generated.dart"), generated).unwrap();
-    std::fs::write(package_dir.join("main.dart"), main).unwrap();
+    std::fs::write(package_dir.join("pubspec.yaml"), pubspec).unwrap();
+    for (path, contents) in files {
+        let path = package_dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
 
     std::mem::forget(tempdir);
 
@@ -251,10 +254,337 @@ fn integration_test() {
     assert!(status.success());
 
     let status = Command::new("dart")
-        .arg("run")
-        .arg("main.dart")
+        .args(args)
         .current_dir(&package_dir)
         .status()
         .unwrap();
     assert!(status.success());
 }
+
+#[test]
+fn integration_test() {
+    let context = Context::from_str(include_str!(crate::test_file!(kitchen_sink))).unwrap();
+    let generated = context.codegen_to_string().unwrap();
+    let main = dart_format(main_dart(), Some("3.8")).unwrap();
+
+    run_dart_package(
+        PUBSPEC,
+        &[("generated.dart", generated), ("main.dart", main)],
+        &["run", "main.dart"],
+    );
+}
+
+const TEST_PUBSPEC: &str = /* yaml */
+    r#"
+name: dart_typegen_test
+environment:
+    sdk: ">3.0.0"
+dev_dependencies:
+    test: ^1.25.0
+"#;
+
+#[test]
+fn generated_tests_pass() {
+    let context = Context::from_str(include_str!(crate::test_file!(kitchen_sink))).unwrap();
+    let generated = context.codegen_to_string().unwrap();
+    let tests = context
+        .codegen_tests_to_string("package:dart_typegen_test/generated.dart")
+        .unwrap();
+
+    run_dart_package(
+        TEST_PUBSPEC,
+        &[
+            ("lib/generated.dart", generated),
+            ("test/generated_test.dart", tests),
+        ],
+        &["test"],
+    );
+}
+
+/// The element types that are wrapped in each of the [`NULLABILITY_SHAPES`], along with two
+/// different values of that type
+const NULLABILITY_ELEMENTS: &[(&str, &str, &str)] = &[
+    ("int", "1", "2"),
+    ("String", "'a'", "'b'"),
+    ("Kind", "Kind.a", "Kind.b"),
+    (
+        "Item",
+        "Item(name: 'a')",
+        "Item(name: 'b', parent: Item(name: 'c'))",
+    ),
+];
+
+/// Every combination of nullable, collection and record types to check, with `T` standing for
+/// the element type. Each shape has two different values, where `$v` and `$w` are values of the
+/// element type and `$t` is the element type itself
+const NULLABILITY_SHAPES: &[(&str, &str, &str)] = &[
+    ("T", "$v", "$w"),
+    ("T?", "$v", "null"),
+    ("List<T>", "[$v, $w]", "<$t>[]"),
+    ("List<T?>", "[$v, null]", "[null]"),
+    ("List<T>?", "[$v]", "null"),
+    ("List<T?>?", "[null, $w]", "null"),
+    ("Set<T>", "{$v}", "<$t>{}"),
+    ("Set<T?>?", "{null, $v}", "null"),
+    ("Map<String, T>", "{'k': $v}", "<String, $t>{}"),
+    ("Map<String, T?>", "{'k': null, 'l': $v}", "{'m': null}"),
+    ("Map<String, T>?", "{'k': $v}", "null"),
+    ("List<List<T?>?>", "[[$v, null], null]", "[null]"),
+    (
+        "List<Map<String, T?>?>",
+        "[{'k': $v, 'l': null}, null]",
+        "[]",
+    ),
+    (
+        "Map<String, List<T?>?>",
+        "{'k': [$v, null], 'l': null}",
+        "{'k': null}",
+    ),
+    ("(T, T?)", "($v, null)", "($w, $v)"),
+    ("(T, List<T?>)?", "($v, [null])", "null"),
+    ("List<(T?, T)?>", "[($v, $w), (null, $v), null]", "[]"),
+];
+
+/// The KDL for a `Matrix` class with a field for each shape and element type, and the Dart to
+/// construct two instances of it which differ in every field
+fn nullability_matrix() -> (String, String, String) {
+    let mut kdl = String::from(
+        r#"
+        class "Item" {
+            field "name" type="String"
+            field "parent" type="Item?" { defaults-to null; }
+        }
+        enum "Kind" {
+            variant "a"
+            variant "b"
+        }
+        class "Matrix" {
+        "#,
+    );
+    let mut first = String::from("Matrix(");
+    let mut second = String::from("Matrix(");
+
+    for (i, (shape, v, w)) in NULLABILITY_SHAPES.iter().enumerate() {
+        for (j, (element, x, y)) in NULLABILITY_ELEMENTS.iter().enumerate() {
+            let name = format!("field{i}x{j}");
+            let value = |template: &str| {
+                template
+                    .replace("$v", x)
+                    .replace("$w", y)
+                    .replace("$t", element)
+            };
+
+            writeln!(kdl, "field {name:?} type={:?}", shape.replace('T', element)).unwrap();
+            write!(first, "{name}: {},", value(v)).unwrap();
+            write!(second, "{name}: {},", value(w)).unwrap();
+        }
+    }
+
+    kdl.push('}');
+    first.push(')');
+    second.push(')');
+
+    (kdl, first, second)
+}
+
+const CHECK_MATRIX: &str = /* dart */
+    r#"
+    void checkMatrix(Matrix obj, Matrix other) {
+        if (obj != obj || obj.hashCode != obj.hashCode) {
+            throw Exception("not equal to self");
+        }
+
+        if (obj == other) {
+            throw Exception("equal to a different value");
+        }
+
+        final decoded = Matrix.fromJson(jsonDecode(jsonEncode(obj.toJson())));
+        if (obj != decoded || obj.hashCode != decoded.hashCode) {
+            throw Exception("json-roundtrip error: $obj != $decoded");
+        }
+
+        if (obj != obj.toBuilder().build()) {
+            throw Exception("builder-roundtrip error");
+        }
+    }
+"#;
+
+#[test]
+fn nullability_matrix_test() {
+    let (kdl, first, second) = nullability_matrix();
+
+    let context = Context::from_str(&kdl).unwrap();
+    context.validate(true).unwrap();
+    let generated = context.codegen_to_string().unwrap();
+
+    let mut main = String::new();
+    writeln!(main, "import 'dart:convert';").unwrap();
+    writeln!(main, "import 'generated.dart';").unwrap();
+    writeln!(main, "void main() {{").unwrap();
+    writeln!(main, "final first = {first};").unwrap();
+    writeln!(main, "final second = {second};").unwrap();
+    writeln!(main, "checkMatrix(first, second);").unwrap();
+    writeln!(main, "checkMatrix(second, first);").unwrap();
+    writeln!(main, "}}").unwrap();
+    writeln!(main, "{CHECK_MATRIX}").unwrap();
+    let main = dart_format(main, Some("3.8")).unwrap();
+
+    run_dart_package(
+        PUBSPEC,
+        &[("generated.dart", generated), ("main.dart", main)],
+        &["run", "main.dart"],
+    );
+}
+
+const FEATURES_PUBSPEC: &str = /* yaml */
+    r#"
+name: dart_typegen_test
+environment:
+    sdk: ">3.0.0"
+dependencies:
+    collection: ^1.18.0
+dev_dependencies:
+    test: ^1.25.0
+"#;
+
+/// Exercises the records, external types, constraints, partial builders, ordering, `when` and
+/// union JSON representations of `features.kdl`. `dart run` doesn't enable asserts, so the
+/// checks throw instead
+const FEATURES_MAIN: &str = /* dart */
+    r#"
+import 'dart:convert';
+import 'generated.dart';
+
+void check(bool condition, String message) {
+    if (!condition) {
+        throw Exception(message);
+    }
+}
+
+void checkThrows<E>(void Function() body, String message) {
+    try {
+        body();
+    } on E {
+        return;
+    }
+    throw Exception(message);
+}
+
+dynamic roundtrip(Map<String, dynamic> json) => jsonDecode(jsonEncode(json));
+
+void main() {
+    final task = Task(
+        title: "Write docs",
+        priority: Priority.low,
+        estimate: Box(3),
+        budget: Money(500),
+        tags: ["docs"],
+        position: (1, 2),
+        size: (width: 1.5, height: 2.5),
+        token: "secret",
+        byPriority: {Priority.low: 1},
+    );
+
+    final decoded = Task.fromJson(roundtrip(task.toJson()));
+    check(decoded == task, "json-roundtrip error: $decoded != $task");
+    check(decoded.hashCode == task.hashCode, "json-roundtrip hashCode error");
+    check(task.toJson()["position"].toString() == "[1, 2]", "records are lists by default");
+    check(task.toJson()["size"]["width"] == 1.5, "size is an object");
+    check(task.toJson()["budget"] == 500, "external types use their to-json");
+
+    check(task.toString().contains("<redacted>"), "token is redacted");
+    check(!task.toString().contains("secret"), "token is not printed");
+    check(task == task.rebuild((b) => b.token = "other"), "token is excluded from ==");
+
+    final renamed = task.rebuild((b) => b.title = "Read docs");
+    check(renamed.title == "Read docs", "rebuild error");
+    check(renamed != task, "rebuilt task is equal");
+    check(task.toBuilder().update((b) => b.tags = ["a", "b"]).build().tags.length == 2, "update error");
+    check((task.toBuilder()..title = "").validate().isNotEmpty, "empty title is valid");
+    check(task.validate().isEmpty, "task is invalid");
+
+    check(Priority.low.compareTo(Priority.high) < 0, "low is not before high");
+    final urgent = task.rebuild((b) => b.priority = Priority.high);
+    final due = task.rebuild((b) => b.due = 1);
+    final sorted = [task, due, renamed, urgent]..sort();
+    check(sorted[0] == urgent, "priority is not descending");
+    check(sorted[1] == due, "nulls are not last");
+    check(sorted[2] == renamed && sorted[3] == task, "titles are not ascending");
+
+    final signUp = SignUp.builder();
+    check(!signUp.isComplete, "empty builder is complete");
+    check(signUp.missingFields.join(", ") == "email, age", "missing: ${signUp.missingFields}");
+    check(signUp.newsletter == false, "newsletter default");
+    check(signUp.validate().isEmpty, "unset fields are validated");
+    checkThrows<StateError>(() => signUp.build(), "incomplete builder builds");
+    signUp.interests.add("sports");
+    signUp
+        ..email = "a@b"
+        ..age = 30;
+    final built = signUp.build();
+    check(built.interests.length == 2, "interests are not modifiable");
+    check(built.validate().isEmpty, "built value is invalid");
+    check(SignUp.fromJson(roundtrip(built.toJson())) == built, "sign up json-roundtrip error");
+    check((built.toBuilder()..age = 5).validate().length == 1, "age is not validated");
+    checkThrows<ArgumentError>(
+        () => SignUp.fromJson({"email": "nope", "age": 30}),
+        "invalid json is accepted",
+    );
+
+    final Event created = Created(task: task);
+    final createdJson = roundtrip(created.toJson());
+    check(createdJson["kind"] == "created", "adjacent discriminant");
+    check(createdJson["data"]["task"]["title"] == "Write docs", "adjacent content");
+    check(Event.fromJson(createdJson) == created, "event json-roundtrip error");
+    check(Event.fromJson(Deleted(title: "x").toJson()) == Deleted(title: "x"), "deleted json-roundtrip error");
+    check(created.when(created: (c) => c.task.title, deleted: (d) => d.title) == "Write docs", "when error");
+    check(created.maybeWhen(deleted: (_) => 1, orElse: () => 2) == 2, "maybeWhen error");
+    check(created.mapOrNull(deleted: (_) => 1) == null, "mapOrNull error");
+    final rebuiltEvent = created.rebuild((b) => (b as CreatedBuilder).task.title = "Other");
+    check((rebuiltEvent as Created).task.title == "Other", "union rebuild error");
+
+    final Payment card = Card(last4: "1234");
+    check(jsonEncode(card.toJson()) == '{"card":{"last4":"1234"}}', "external representation");
+    check(jsonEncode(Cash().toJson()) == '{"cash":{}}', "external representation of empty members");
+    check(Payment.fromJson(roundtrip(card.toJson())) == card, "payment json-roundtrip error");
+    check(Payment.fromJson({"cash": {}}) == Cash(), "cash json error");
+
+    final Contact phone = PhoneContact(number: "555");
+    check(jsonEncode(phone.toJson()) == '{"number":"555"}', "untagged representation");
+    check(Contact.fromJson({"number": "555"}) == phone, "untagged decoding");
+    check(Contact.fromJson({"address": "a@b"}) == EmailContact(address: "a@b"), "untagged decoding");
+    checkThrows<ArgumentError>(() => Contact.fromJson({}), "unknown contact is accepted");
+}
+"#;
+
+#[test]
+fn features_integration_test() {
+    let context = Context::from_str(include_str!(crate::test_file!(features))).unwrap();
+    context.validate(true).unwrap();
+    let generated = context.codegen_to_string().unwrap();
+    let main = dart_format(FEATURES_MAIN.to_string(), Some("3.8")).unwrap();
+
+    run_dart_package(
+        FEATURES_PUBSPEC,
+        &[("generated.dart", generated), ("main.dart", main)],
+        &["run", "main.dart"],
+    );
+}
+
+#[test]
+fn features_generated_tests_pass() {
+    let context = Context::from_str(include_str!(crate::test_file!(features))).unwrap();
+    let generated = context.codegen_to_string().unwrap();
+    let tests = context
+        .codegen_tests_to_string("package:dart_typegen_test/generated.dart")
+        .unwrap();
+
+    run_dart_package(
+        FEATURES_PUBSPEC,
+        &[
+            ("lib/generated.dart", generated),
+            ("test/generated_test.dart", tests),
+        ],
+        &["test"],
+    );
+}
@@ -27,26 +27,44 @@ macro_rules! all_test_files {
     ($macro_name:ident) => {
         $macro_name!(class_docs);
         $macro_name!(class_extra_dart);
+        $macro_name!(class_field_constraints);
         $macro_name!(class_field_default);
+        $macro_name!(class_field_enum_default);
+        $macro_name!(class_field_exclusions);
+        $macro_name!(class_field_generics);
+        $macro_name!(class_field_map_keys);
+        $macro_name!(class_field_nullability);
+        $macro_name!(class_field_records);
+        $macro_name!(class_field_structured_default);
         $macro_name!(class_field_docs);
+        $macro_name!(class_modifiers);
+        $macro_name!(class_ordering);
+        $macro_name!(class_partial_builder);
         $macro_name!(class_simple);
         $macro_name!(class_with_class_field);
         $macro_name!(default_field_case);
         $macro_name!(default_sealed_unions);
+        $macro_name!(embedded_examples);
         $macro_name!(empty);
         $macro_name!(enum_docs);
         $macro_name!(enum_extra_dart);
         $macro_name!(enum_json_value);
         $macro_name!(enum_simple);
+        $macro_name!(external_type);
+        $macro_name!(features);
         $macro_name!(kitchen_sink);
         $macro_name!(type_list);
         $macro_name!(postamble);
         $macro_name!(preamble);
+        $macro_name!(string_escaping);
         $macro_name!(union_docs);
         $macro_name!(union_extra_dart);
         $macro_name!(union_json_discriminant);
+        $macro_name!(union_json_representation);
+        $macro_name!(union_nested);
         $macro_name!(union_sealed);
         $macro_name!(union_simple);
+        $macro_name!(union_when);
     };
 }
 
@@ -13,3 +13,31 @@ macro_rules! output_snapshot {
 }
 
 crate::all_test_files!(output_snapshot);
+
+mod json_examples {
+    use crate::{context::Context, json::ExampleMode};
+
+    macro_rules! json_examples_snapshot {
+        ($name:ident) => {
+            #[test]
+            fn $name() {
+                let context = Context::from_str(include_str!(crate::test_file!($name))).unwrap();
+
+                let mut output = String::new();
+                for mode in [ExampleMode::Minimal, ExampleMode::Maximal] {
+                    for (name, example) in context.json_examples(mode) {
+                        let example = match example {
+                            Some(example) => example.to_string(),
+                            None => "no example".to_string(),
+                        };
+                        output.push_str(&format!("// {name} ({mode:?})\n{example}\n"));
+                    }
+                }
+
+                insta::assert_snapshot!(stringify!($name), output);
+            }
+        };
+    }
+
+    crate::all_test_files!(json_examples_snapshot);
+}
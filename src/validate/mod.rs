@@ -1,13 +1,20 @@
 use std::collections::HashMap;
 
 use convert_case::{Case, Casing};
-use knus::ast::{Integer, Literal, Radix};
+use knus::{
+    ast::{Integer, Literal, Radix, Value},
+    span::Span,
+};
 use miette::{Diagnostic, NamedSource, Result, Severity, SourceSpan};
 use thiserror::Error;
 
 use crate::{
-    context::{Context, TyKind},
-    model::Field,
+    context::{Context, Ty, TyKind, is_valid_identifier, record_fields},
+    json::parse_embedded,
+    model::{
+        BuilderMode, Class, ClassModifier, DefaultValue, Equality, Field, JsonRepresentation,
+        RecordRepresentation, SpannedScalar, Union,
+    },
 };
 
 #[cfg(test)]
@@ -40,16 +47,39 @@ impl Context {
         let source = self.named_source();
 
         incorrect_type_name_case(self, &mut errors, &source);
+        invalid_identifiers(self, &mut errors, &source);
         duplicate_type_names(self, &mut errors, &source);
         duplicate_field_names(self, &mut errors, &source);
         empty_union(self, &mut errors, &source);
         field_with_both_defaults(self, &mut errors, &source);
         invalid_int_literal(self, &mut errors, &source);
+        invalid_default_types(self, &mut errors, &source);
+        invalid_constraints(self, &mut errors, &source);
         empty_enum(self, &mut errors, &source);
         json_discrimminant_non_union_class(self, &mut errors, &source);
+        nested_discriminant_key_collision(self, &mut errors, &source);
         duplicate_json_keys(self, &mut errors, &source);
+        duplicate_discriminant_values(self, &mut errors, &source);
+        field_discriminant_key_collision(self, &mut errors, &source);
         invalid_field_types(self, &mut errors, &source);
+        unknown_types(self, &mut errors, &source);
+        one_way_external_conversion(self, &mut errors, &source);
+        imports_in_part_files(self, &mut errors, &source);
+        invalid_class_hierarchy(self, &mut errors, &source);
+        when_parameter_collision(self, &mut errors, &source);
+        invalid_json_representation(self, &mut errors, &source);
         version_too_low(self, &mut errors, &source);
+        invalid_orderings(self, &mut errors, &source);
+        generated_member_collisions(self, &mut errors, &source);
+
+        // Examples are checked against the generated `fromJson`, which is only meaningful once
+        // the rest of the config is valid (warnings aside)
+        let is_valid = errors
+            .iter()
+            .all(|e| e.severity() == Some(Severity::Warning));
+        if is_valid {
+            invalid_examples(self, &mut errors, &source);
+        }
 
         errors
     }
@@ -110,6 +140,114 @@ fn incorrect_type_name_case(
     errors.extend(incorrect_class_name_case.map(Into::into));
 }
 
+// === Invalid Identifiers ===
+
+/// Words that can never be used as identifiers in Dart
+pub(crate) const RESERVED_WORDS: &[&str] = &[
+    "assert", "break", "case", "catch", "class", "const", "continue", "default", "do", "else",
+    "enum", "extends", "false", "final", "finally", "for", "if", "in", "is", "new", "null",
+    "rethrow", "return", "super", "switch", "this", "throw", "true", "try", "var", "void", "while",
+    "with",
+];
+
+/// Words that can be used as most identifiers in Dart, but not as the name of a type
+pub(crate) const BUILT_IN_IDENTIFIERS: &[&str] = &[
+    "abstract",
+    "as",
+    "covariant",
+    "deferred",
+    "dynamic",
+    "export",
+    "extension",
+    "external",
+    "factory",
+    "Function",
+    "get",
+    "implements",
+    "import",
+    "interface",
+    "late",
+    "library",
+    "mixin",
+    "operator",
+    "part",
+    "required",
+    "set",
+    "static",
+    "typedef",
+];
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{name}` cannot be used as the name of a {kind} in Dart, {reason}")]
+struct InvalidIdentifier {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    name: String,
+    kind: &'static str,
+    reason: &'static str,
+
+    #[help]
+    help: String,
+}
+
+fn invalid_identifiers(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+
+    let mut check = |name: &SpannedScalar<String>, kind: &'static str, help: &str| {
+        let reason = if !is_valid_identifier(name) {
+            "since it is not a valid identifier"
+        } else if RESERVED_WORDS.contains(&name.as_str()) {
+            "since it is a reserved word"
+        } else if kind == "type" && BUILT_IN_IDENTIFIERS.contains(&name.as_str()) {
+            "since it is a built-in identifier"
+        } else {
+            return;
+        };
+
+        let err = InvalidIdentifier {
+            src: source.clone(),
+            span: name.span,
+            name: name.to_string(),
+            kind,
+            reason,
+            help: help.replace("{name}", name),
+        };
+        errors.push(err.into());
+    };
+
+    for name in library.type_names() {
+        check(name, "type", "Try giving it a different name");
+    }
+
+    for field in library.all_fields() {
+        check(
+            &field.name,
+            "field",
+            r#"Try renaming the field and adding `json-key "{name}"` to keep the same JSON key"#,
+        );
+    }
+
+    for variant in library
+        .enums
+        .iter()
+        .flat_map(|enumeration| &enumeration.variants)
+    {
+        check(
+            &variant.name,
+            "variant",
+            r#"Try renaming the variant and adding `json-value "{name}"` to keep the same JSON value"#,
+        );
+    }
+}
+
 // === Duplicate class names ===
 
 #[derive(Debug, Error, Diagnostic)]
@@ -127,17 +265,20 @@ fn duplicate_type_names(
     errors: &mut Vec<miette::Report>,
     source: &NamedSource<String>,
 ) {
+    let external_names = context.library.external_types.iter().map(|e| &e.name);
+    let names: Vec<_> = context.library.type_names().chain(external_names).collect();
+
     let mut name_counts = HashMap::<_, usize>::new();
-    for name in context.library.type_names() {
+    for name in &names {
         *name_counts.entry(name.value.as_str()).or_default() += 1usize;
     }
 
-    for class in &context.library.classes {
-        if name_counts[class.name.as_str()] > 1 {
+    for name in names {
+        if name_counts[name.as_str()] > 1 {
             errors.push(
                 DuplicateTypeName {
                     src: source.to_owned(),
-                    source_span: class.name.span,
+                    source_span: name.span,
                 }
                 .into(),
             );
@@ -162,7 +303,7 @@ fn duplicate_field_names(
     errors: &mut Vec<miette::Report>,
     source: &NamedSource<String>,
 ) {
-    for class in &context.library.classes {
+    for class in context.library.all_classes() {
         let mut name_counts = HashMap::<_, usize>::new();
         for field in &class.fields {
             *name_counts.entry(field.name.value.as_str()).or_default() += 1usize;
@@ -186,7 +327,7 @@ fn duplicate_field_names(
 
 #[derive(Debug, Error, Diagnostic)]
 #[error("Union was empty")]
-#[diagnostic(help = "Unions must contain at least one `class`")]
+#[diagnostic(help = "Unions must contain at least one `class` or `union`")]
 struct EmptyUnion {
     #[source_code]
     src: NamedSource<String>,
@@ -197,9 +338,8 @@ struct EmptyUnion {
 fn empty_union(context: &Context, errors: &mut Vec<miette::Report>, source: &NamedSource<String>) {
     let errs = context
         .library
-        .unions
-        .iter()
-        .filter(|union| union.classes.is_empty())
+        .all_unions()
+        .filter(|union| union.classes.is_empty() && union.unions.is_empty())
         .map(|union| EmptyUnion {
             src: source.clone(),
             source_span: union.span.into(),
@@ -237,7 +377,7 @@ fn field_with_both_defaults(
 
         Some(FieldWithBothDefaults {
             src: source.clone(),
-            defaults_to: (*defaults_to.literal.span()).into(),
+            defaults_to: default_span(defaults_to),
             defaults_to_dart: defaults_to_dart.span,
         })
     });
@@ -283,6 +423,525 @@ fn invalid_int_literal(
     errors.extend(errs.map(Into::into));
 }
 
+// === Default Type Mismatches ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Default value does not match the field type `{ty}`")]
+struct DefaultTypeMismatch {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("{found}")]
+    span: SourceSpan,
+
+    ty: String,
+    found: &'static str,
+
+    #[help]
+    help: Option<String>,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{reason}")]
+struct InvalidDefault {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    reason: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+fn invalid_default_types(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    for field in context.library.all_fields() {
+        let Some(defaults_to) = &field.defaults_to else {
+            continue;
+        };
+        let Some(ty) = context.parse_ty(&field.ty).0 else {
+            continue;
+        };
+
+        check_default(context, errors, source, &ty, defaults_to);
+    }
+}
+
+/// The span of the value of a default, or of the whole node for structured defaults
+fn default_span(default: &DefaultValue) -> SourceSpan {
+    match default.value() {
+        Some(value) => (*value.literal.span()).into(),
+        None => default.span.into(),
+    }
+}
+
+/// Checks that `default` can be used as a value of type `ty`, recursing into structured defaults
+fn check_default(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+    ty: &Ty,
+    default: &DefaultValue,
+) {
+    let mut invalid = |span: Span, reason: String, help: Option<&str>| {
+        let err = InvalidDefault {
+            src: source.clone(),
+            span: span.into(),
+            reason,
+            help: help.map(str::to_string),
+        };
+        errors.push(err.into());
+    };
+
+    let has_children =
+        !default.items.is_empty() || !default.entries.is_empty() || !default.fields.is_empty();
+
+    for extra in default.values.iter().skip(1) {
+        invalid(
+            *extra.literal.span(),
+            "A default can only have one value".to_string(),
+            Some("Use `item` children to write a list"),
+        );
+    }
+
+    if let Some(value) = default.value() {
+        if has_children {
+            invalid(
+                default.span,
+                "A default can't have both a value and children".to_string(),
+                None,
+            );
+        }
+
+        check_scalar(context, errors, source, ty, value);
+        return;
+    }
+
+    let inner = match &ty.kind {
+        TyKind::Nullable(inner) => &**inner,
+        _ => ty,
+    };
+
+    let mut unexpected = |children: &[Span], name: &str| {
+        for span in children {
+            invalid(
+                *span,
+                format!("`{name}` can't be used in a default of type `{ty}`"),
+                None,
+            );
+        }
+    };
+
+    let item_spans: Vec<_> = default.items.iter().map(|item| item.span).collect();
+    let entry_spans: Vec<_> = default.entries.iter().map(|entry| entry.span).collect();
+    let field_spans: Vec<_> = default.fields.iter().map(|field| field.span).collect();
+
+    match &inner.kind {
+        TyKind::List(item_ty) | TyKind::Set(item_ty) => {
+            unexpected(&entry_spans, "entry");
+            unexpected(&field_spans, "field");
+
+            for item in &default.items {
+                check_default(context, errors, source, item_ty, item);
+            }
+        }
+        TyKind::Map { key, value } => {
+            unexpected(&item_spans, "item");
+            unexpected(&field_spans, "field");
+
+            for entry in &default.entries {
+                check_scalar(context, errors, source, key, &entry.key);
+                check_default(context, errors, source, value, &entry.to_default_value());
+            }
+        }
+        TyKind::Simple(name) => {
+            let Some(class) = context
+                .library
+                .all_classes()
+                .find(|class| class.name.as_str() == name)
+            else {
+                let err = DefaultTypeMismatch {
+                    src: source.clone(),
+                    span: default.span.into(),
+                    ty: ty.to_string(),
+                    found: "a structured value",
+                    help: Some(
+                        "Structured defaults can only be used for lists, sets, maps and generated classes"
+                            .to_string(),
+                    ),
+                };
+                errors.push(err.into());
+                return;
+            };
+
+            unexpected(&item_spans, "item");
+            unexpected(&entry_spans, "entry");
+
+            check_class_default(context, errors, source, class, default);
+        }
+        TyKind::Record { .. } | TyKind::Generic { .. } => {
+            let err = DefaultTypeMismatch {
+                src: source.clone(),
+                span: default.span.into(),
+                ty: ty.to_string(),
+                found: "a structured value",
+                help: Some(format!("Use `defaults-to-dart` to write a `{inner}`")),
+            };
+            errors.push(err.into());
+        }
+        TyKind::Nullable(_) => {}
+    }
+}
+
+/// Checks the `field` children of a structured default for a generated class
+fn check_class_default(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+    class: &Class,
+    default: &DefaultValue,
+) {
+    let mut assigned: Vec<&str> = vec![];
+
+    for assignment in &default.fields {
+        let mut invalid = |reason: String, help: Option<String>| {
+            let err = InvalidDefault {
+                src: source.clone(),
+                span: (*assignment.key.literal.span()).into(),
+                reason,
+                help,
+            };
+            errors.push(err.into());
+        };
+
+        let Literal::String(name) = &*assignment.key.literal else {
+            invalid("Field names must be strings".to_string(), None);
+            continue;
+        };
+
+        let Some(field) = class
+            .fields
+            .iter()
+            .find(|field| field.name.as_str() == &**name)
+        else {
+            let names: Vec<_> = class
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect();
+            let help = closest_name(name, &names).map(|name| format!("did you mean `{name}`?"));
+            invalid(format!("`{}` has no field `{name}`", class.name), help);
+            continue;
+        };
+
+        if assigned.contains(&&**name) {
+            invalid(format!("Field `{name}` is set more than once"), None);
+            continue;
+        }
+        assigned.push(name);
+
+        if let Some(ty) = context.parse_ty(&field.ty).0 {
+            check_default(context, errors, source, &ty, &assignment.to_default_value());
+        }
+    }
+
+    let missing = class.fields.iter().filter(|field| {
+        field.defaults_to.is_none()
+            && field.defaults_to_dart.is_none()
+            && !assigned.contains(&field.name.as_str())
+    });
+
+    for field in missing {
+        let err = InvalidDefault {
+            src: source.clone(),
+            span: default.span.into(),
+            reason: format!(
+                "Default for `{}` is missing the required field `{}`",
+                class.name, field.name
+            ),
+            help: Some(format!(r#"Add `field "{}" ...`"#, field.name)),
+        };
+        errors.push(err.into());
+    }
+}
+
+fn check_scalar(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+    ty: &Ty,
+    value: &Value<Span>,
+) {
+    let literal = &*value.literal;
+    let Err(help) = check_scalar_default(context, ty, literal) else {
+        return;
+    };
+
+    let found = match literal {
+        Literal::Null => "null",
+        Literal::Bool(_) => "a bool",
+        Literal::Int(_) => "an int",
+        Literal::Decimal(_) => "a decimal",
+        Literal::String(_) => "a string",
+    };
+
+    let err = DefaultTypeMismatch {
+        src: source.clone(),
+        span: (*value.literal.span()).into(),
+        ty: ty.to_string(),
+        found,
+        help,
+    };
+    errors.push(err.into());
+}
+
+/// Checks that `literal` can be used as a value of type `ty`, returning some help text if it
+/// can't
+fn check_scalar_default(
+    context: &Context,
+    ty: &Ty,
+    literal: &Literal,
+) -> std::result::Result<(), Option<String>> {
+    let (nullable, inner) = match &ty.kind {
+        TyKind::Nullable(inner) => (true, &**inner),
+        _ => (false, ty),
+    };
+
+    if let Literal::Null = literal {
+        return match nullable {
+            true => Ok(()),
+            false => Err(Some(
+                "Only nullable fields can default to `null`".to_string(),
+            )),
+        };
+    }
+
+    let name = match &inner.kind {
+        TyKind::Simple(name) => name,
+        TyKind::Record { .. } | TyKind::Generic { .. } => {
+            return Err(Some(format!("Use `defaults-to-dart` to write a `{inner}`")));
+        }
+        _ => {
+            return Err(Some(
+                "Use `item` or `entry` children to write a collection".to_string(),
+            ));
+        }
+    };
+
+    let matches = match (name.as_str(), literal) {
+        ("dynamic" | "Object", _) => true,
+        ("int", Literal::Int(_)) => true,
+        ("double" | "num", Literal::Int(_) | Literal::Decimal(_)) => true,
+        ("String", Literal::String(_)) => true,
+        ("bool", Literal::Bool(_)) => true,
+        ("int" | "double" | "num" | "String" | "bool", _) => false,
+        _ => return check_enum_default(context, ty, name, literal),
+    };
+
+    match matches {
+        true => Ok(()),
+        false => Err(None),
+    }
+}
+
+fn check_enum_default(
+    context: &Context,
+    ty: &Ty,
+    name: &str,
+    literal: &Literal,
+) -> std::result::Result<(), Option<String>> {
+    let Some(enumeration) = context.ty_enum(ty) else {
+        // types generated by this config can't be written as scalars, and other types (e.g.
+        // from the preamble) can't be checked
+        return match context.library.type_has_builder(name) {
+            true => Err(Some(
+                "Use `field` children to write a class, or `defaults-to-dart`".to_string(),
+            )),
+            false => Ok(()),
+        };
+    };
+
+    let variants: Vec<_> = enumeration
+        .variants
+        .iter()
+        .map(|variant| variant.name.as_str())
+        .collect();
+
+    let Literal::String(value) = literal else {
+        return Err(Some(format!(
+            "Use the name of a variant of `{}` as a string",
+            enumeration.name
+        )));
+    };
+
+    if variants.contains(&&**value) {
+        return Ok(());
+    }
+
+    let help = match closest_name(value, &variants) {
+        Some(closest) => format!("did you mean `{closest}`?"),
+        None => {
+            let variants: Vec<_> = variants
+                .iter()
+                .map(|variant| format!("`{variant}`"))
+                .collect();
+            format!(
+                "`{}` has the variants {}",
+                enumeration.name,
+                variants.join(", ")
+            )
+        }
+    };
+
+    Err(Some(help))
+}
+
+// === Invalid Constraints ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{reason}")]
+struct InvalidConstraint {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    reason: String,
+}
+
+fn invalid_constraints(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let mut push = |span: SourceSpan, reason: String| {
+        let err = InvalidConstraint {
+            src: source.clone(),
+            span,
+            reason,
+        };
+        errors.push(err.into());
+    };
+
+    for field in context.library.all_fields() {
+        let Some(ty) = context.parse_ty(&field.ty).0 else {
+            continue;
+        };
+        let ty = match ty.kind {
+            TyKind::Nullable(inner) => *inner,
+            _ => ty,
+        };
+
+        let simple = match &ty.kind {
+            TyKind::Simple(name) => Some(name.as_str()),
+            _ => None,
+        };
+        let numeric = matches!(simple, Some("int" | "double" | "num"));
+        let string = simple == Some("String");
+        let collection = matches!(
+            ty.kind,
+            TyKind::List(_) | TyKind::Set(_) | TyKind::Map { .. }
+        );
+
+        let mut bounds = vec![];
+        for (name, bound) in [("min", &field.min), ("max", &field.max)] {
+            let Some(bound) = bound else { continue };
+            let span = (*bound.literal.span()).into();
+
+            if !numeric {
+                push(span, format!("`{name}` can only be used on numeric fields"));
+                continue;
+            }
+
+            let value = match &*bound.literal {
+                Literal::Int(int) => i64::try_from(int).ok().map(|value| value as f64),
+                Literal::Decimal(decimal) if simple != Some("int") => decimal.0.parse().ok(),
+                _ => None,
+            };
+
+            match value {
+                Some(value) => bounds.push((span, value)),
+                None => push(
+                    span,
+                    format!("`{name}` must be a number that fits in the field type `{ty}`"),
+                ),
+            }
+        }
+
+        for (name, constraint, allowed, kind) in [
+            (
+                "pattern",
+                field.pattern.as_ref().map(|p| p.span),
+                string,
+                "`String`",
+            ),
+            (
+                "min-length",
+                field.min_length.map(|l| l.span),
+                string,
+                "`String`",
+            ),
+            (
+                "max-length",
+                field.max_length.map(|l| l.span),
+                string,
+                "`String`",
+            ),
+            (
+                "min-items",
+                field.min_items.map(|l| l.span),
+                collection,
+                "collection",
+            ),
+            (
+                "max-items",
+                field.max_items.map(|l| l.span),
+                collection,
+                "collection",
+            ),
+        ] {
+            if let Some(span) = constraint
+                && !allowed
+            {
+                push(span, format!("`{name}` can only be used on {kind} fields"));
+            }
+        }
+
+        if let [(_, min), (max_span, max)] = bounds[..]
+            && min > max
+        {
+            push(max_span, "`max` is smaller than `min`".to_string());
+        }
+
+        let lengths = [
+            (
+                &field.min_length,
+                &field.max_length,
+                "max-length",
+                "min-length",
+            ),
+            (&field.min_items, &field.max_items, "max-items", "min-items"),
+        ];
+        for (min, max, max_name, min_name) in lengths {
+            if let (Some(min), Some(max)) = (min, max)
+                && min.value > max.value
+            {
+                push(
+                    max.span,
+                    format!("`{max_name}` is smaller than `{min_name}`"),
+                );
+            }
+        }
+    }
+}
+
 // === Invalid Int Literals ===
 
 #[derive(Debug, Error, Diagnostic)]
@@ -338,20 +997,104 @@ fn json_discrimminant_non_union_class(
     });
 
     errors.extend(errs.map(Into::into));
+
+    let library = &context.library;
+    let errs = library.all_unions().filter_map(|union| {
+        let value = union.json_discriminant_value.as_ref()?;
+
+        let used = library.parent_union(union).is_some_and(|parent| {
+            library.discriminant_key_for(parent) != library.discriminant_key_for(union)
+        });
+
+        if used {
+            return None;
+        }
+
+        Some(UnusedUnionDiscriminantValue {
+            src: source.clone(),
+            span: (*value.literal.span()).into(),
+        })
+    });
+
+    errors.extend(errs.map(Into::into));
 }
 
-// === Duplicate Json Keys ===
+#[derive(Debug, Error, Diagnostic)]
+#[error("Union has a `json-discriminant-value` that is never used")]
+#[help = "Only nested unions with a different `json-discriminant` to their parent are identified by a discriminant value"]
+struct UnusedUnionDiscriminantValue {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label(primary, "Remove this")]
+    span: SourceSpan,
+}
+
+// === Nested Discriminant Keys ===
 
 #[derive(Debug, Error, Diagnostic)]
-#[error("Multiple fields have the same json key")]
-struct DuplicateJsonKeys {
+#[error("Nested union reuses the discriminant key `{key}` of an outer union")]
+#[diagnostic(
+    help = "A nested union must either share its parent's key, or use a key that no enclosing union uses"
+)]
+struct NestedDiscriminantKeyCollision {
     #[source_code]
     src: NamedSource<String>,
 
     key: String,
 
-    #[label("first field")]
-    first: SourceSpan,
+    #[label("defined here")]
+    span: SourceSpan,
+}
+
+fn nested_discriminant_key_collision(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+
+    for union in library.all_unions() {
+        let Some(key) = &union.json_discriminant else {
+            continue;
+        };
+
+        let ancestors = library.union_ancestors(union);
+        let [outer @ .., parent, _] = ancestors.as_slice() else {
+            continue;
+        };
+
+        if library.discriminant_key_for(parent) == key.as_str() {
+            continue;
+        }
+
+        let collides = outer
+            .iter()
+            .any(|ancestor| library.discriminant_key_for(ancestor) == key.as_str());
+
+        if collides {
+            let err = NestedDiscriminantKeyCollision {
+                src: source.clone(),
+                key: key.to_string(),
+                span: key.span,
+            };
+            errors.push(err.into());
+        }
+    }
+}
+
+// === Duplicate Json Keys ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Multiple fields have the same json key")]
+struct DuplicateJsonKeys {
+    #[source_code]
+    src: NamedSource<String>,
+
+    key: String,
+
+    #[label("first field")]
+    first: SourceSpan,
 
     #[label("second field")]
     second: SourceSpan,
@@ -390,6 +1133,155 @@ fn json_key_span(field: &Field) -> SourceSpan {
         .unwrap_or(field.name.span)
 }
 
+// === Discriminant Collisions ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Multiple members of union `{union}` use the discriminant value {value}")]
+#[diagnostic(
+    help = "Give each member a unique `json-discriminant-value`, or rename one of the members"
+)]
+struct DuplicateDiscriminantValue {
+    #[source_code]
+    src: NamedSource<String>,
+
+    union: String,
+    value: String,
+
+    #[label(collection, "discriminant value {value}")]
+    spans: Vec<SourceSpan>,
+}
+
+fn duplicate_discriminant_values(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+
+    for union in library.all_unions() {
+        // untagged unions have no discriminant values
+        if library.json_representation_for(union) == JsonRepresentation::Untagged {
+            continue;
+        }
+
+        // nested unions which share their parent's key are checked as part of the parent
+        let flattened = library.parent_union(union).is_some_and(|parent| {
+            library.discriminant_key_for(parent) == library.discriminant_key_for(union)
+        });
+        if flattened {
+            continue;
+        }
+
+        let entries = library.discriminant_entries(union);
+
+        for (index, entry) in entries.iter().enumerate() {
+            let seen_before = entries[..index]
+                .iter()
+                .any(|previous| previous.value == entry.value);
+            if seen_before {
+                continue;
+            }
+
+            let spans: Vec<_> = entries[index..]
+                .iter()
+                .filter(|other| other.value == entry.value)
+                .map(|other| other.span)
+                .collect();
+
+            if spans.len() > 1 {
+                let err = DuplicateDiscriminantValue {
+                    src: source.clone(),
+                    union: union.name.to_string(),
+                    value: entry.value.to_string(),
+                    spans,
+                };
+                errors.push(err.into());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "Field `{field}` of `{class}` uses the json key `{key}`, which is also the discriminant key of union `{union}`"
+)]
+#[diagnostic(help = "Use a different `json-key` for the field, or a different `json-discriminant`")]
+struct FieldDiscriminantKeyCollision {
+    #[source_code]
+    src: NamedSource<String>,
+
+    field: String,
+    class: String,
+    key: String,
+    union: String,
+
+    #[label("field")]
+    field_span: SourceSpan,
+
+    #[label("discriminant key")]
+    key_span: SourceSpan,
+}
+
+fn field_discriminant_key_collision(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+
+    // only internally tagged unions put the discriminant next to the fields
+    let unions = library
+        .all_unions()
+        .filter(|union| library.json_representation_for(union) == JsonRepresentation::Internal);
+
+    for union in unions {
+        for class in &union.classes {
+            for field in &class.fields {
+                let json_key = library.json_key_for(class, field);
+
+                for ancestor in library.union_ancestors(union) {
+                    let key = library.discriminant_key_for(ancestor);
+                    if key != json_key {
+                        continue;
+                    }
+
+                    let err = FieldDiscriminantKeyCollision {
+                        src: source.clone(),
+                        field: field.name.to_string(),
+                        class: class.name.to_string(),
+                        key: key.to_string(),
+                        union: ancestor.name.to_string(),
+                        field_span: json_key_span(field),
+                        key_span: discriminant_key_span(context, ancestor),
+                    };
+                    errors.push(err.into());
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// The span of the setting that decides the discriminant key of `union`, falling back to the
+/// name of the union if the key is the implicit default
+fn discriminant_key_span(context: &Context, union: &Union) -> SourceSpan {
+    let library = &context.library;
+
+    if let Some(key) = &union.json_discriminant {
+        return key.span;
+    }
+
+    if let Some(parent) = library.parent_union(union) {
+        return discriminant_key_span(context, parent);
+    }
+
+    library
+        .defaults
+        .as_ref()
+        .and_then(|d| d.union.as_ref()?.json_discriminant.as_ref())
+        .map_or(union.name.span, |key| key.span)
+}
+
 // === Invalid Field Types ===
 
 #[derive(Debug, Error, Diagnostic)]
@@ -402,7 +1294,7 @@ struct InvalidFieldType {
     span: SourceSpan,
 
     #[help]
-    message: &'static str,
+    message: String,
 }
 
 fn invalid_field_types(
@@ -420,25 +1312,1305 @@ fn invalid_field_types(
             let err = InvalidFieldType {
                 src: source.clone(),
                 span: field.ty.span,
-                message: "Failed to parse type",
+                message: "Failed to parse type".to_string(),
             };
 
             errors.push(err.into());
             return;
         };
 
-        if let TyKind::Map { key, .. } = &ty.kind {
-            match &key.kind {
-                TyKind::Simple(s) if s == "String" => {}
-                _ => {
-                    let err = InvalidFieldType {
-                        src: source.clone(),
-                        span: key.span.into(),
-                        message: "Only `String` is supported as a Map key type",
-                    };
+        let custom_json = field.to_json.is_some() && field.from_json.is_some();
 
-                    errors.push(err.into());
+        let mut invalid = |span: Span, message: String| {
+            let err = InvalidFieldType {
+                src: source.clone(),
+                span: span.into(),
+                message,
+            };
+            errors.push(err.into());
+        };
+
+        for ty in ty.nested() {
+            if let Some(message) = wrong_type_arguments(ty) {
+                invalid(ty.span, message);
+                continue;
+            }
+
+            // other generic types can only be converted by an external type with the same name
+            if let TyKind::Generic { name, .. } = &ty.kind
+                && !custom_json
+                && !has_json_codec(context, name)
+            {
+                let message = format!(
+                    "`{ty}` can't be converted to and from JSON. Declare `external-type \"{name}\"` \
+                     with `to-json` and `from-json`, or give the field its own conversions"
+                );
+                invalid(ty.span, message);
+            }
+        }
+
+        invalid_map_keys(context, errors, source, &ty);
+        invalid_records(context, errors, source, field, &ty);
+    }
+}
+
+fn has_json_codec(context: &Context, name: &str) -> bool {
+    context
+        .library
+        .external_type(name)
+        .is_some_and(|external| external.to_json.is_some() && external.from_json.is_some())
+}
+
+/// Checks the number of type arguments of the built-in collections
+fn wrong_type_arguments(ty: &Ty) -> Option<String> {
+    let (name, count) = match &ty.kind {
+        TyKind::Simple(name) => (name, 0),
+        TyKind::Generic { name, args } => (name, args.len()),
+        _ => return None,
+    };
+
+    let expected = match name.as_str() {
+        "List" | "Set" => 1,
+        "Map" => 2,
+        _ => return None,
+    };
+
+    let example = match expected {
+        1 => format!("`{name}<String>`"),
+        _ => format!("`{name}<String, int>`"),
+    };
+
+    match count {
+        0 => Some(format!("`{name}` needs type arguments, e.g. {example}")),
+        _ => Some(format!(
+            "`{name}` takes {expected} type argument{}, e.g. {example}",
+            if expected == 1 { "" } else { "s" }
+        )),
+    }
+}
+
+// === Invalid Map Keys ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{ty}` can't be used as a Map key")]
+struct InvalidMapKey {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    ty: String,
+
+    #[help]
+    help: String,
+}
+
+/// JSON object keys are strings, so map keys must have a conversion to and from a string
+fn invalid_map_keys(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+    ty: &Ty,
+) {
+    match &ty.kind {
+        TyKind::Simple(_) => {}
+        TyKind::List(inner) | TyKind::Set(inner) | TyKind::Nullable(inner) => {
+            invalid_map_keys(context, errors, source, inner)
+        }
+        TyKind::Record { positional, named } => {
+            for (_, ty) in record_fields(positional, named) {
+                invalid_map_keys(context, errors, source, ty);
+            }
+        }
+        TyKind::Generic { args, .. } => {
+            for arg in args {
+                invalid_map_keys(context, errors, source, arg);
+            }
+        }
+        TyKind::Map { key, value } => {
+            if let Err(help) = check_map_key(context, key) {
+                let err = InvalidMapKey {
+                    src: source.clone(),
+                    span: key.span.into(),
+                    ty: key.to_string(),
+                    help,
+                };
+                errors.push(err.into());
+            }
+
+            invalid_map_keys(context, errors, source, value);
+        }
+    }
+}
+
+fn check_map_key(context: &Context, key: &Ty) -> std::result::Result<(), String> {
+    const SUPPORTED: &str = concat!(
+        "Map keys can be `String`, `int`, `double`, an enum, ",
+        "or an `external-type` with `to-json` and `from-json`"
+    );
+
+    let TyKind::Simple(name) = &key.kind else {
+        return Err(format!(
+            "Map keys can't be nullable, collections, records or generic types. {SUPPORTED}"
+        ));
+    };
+
+    if let "String" | "int" | "double" = name.as_str() {
+        return Ok(());
+    }
+
+    if let Some(enumeration) = context.ty_enum(key) {
+        let non_string = enumeration.variants.iter().find(|variant| {
+            variant
+                .json_value
+                .as_ref()
+                .is_some_and(|value| !matches!(*value.literal, Literal::String(_)))
+        });
+
+        return match non_string {
+            Some(variant) => Err(format!(
+                "Enums used as Map keys must have string JSON values, but `{}.{}` doesn't",
+                enumeration.name, variant.name
+            )),
+            None => Ok(()),
+        };
+    }
+
+    if context.library.type_has_builder(name) {
+        return Err(format!(
+            "Generated classes and unions don't have a string representation. {SUPPORTED}"
+        ));
+    }
+
+    if let Some(external) = context.library.external_type(name) {
+        if external.to_json.is_none() || external.from_json.is_none() {
+            return Err(format!(
+                "`{name}` needs both `to-json` and `from-json` to convert keys to and from strings"
+            ));
+        }
+
+        if context.library.equality_for(name) != Equality::Shallow {
+            return Err(
+                "Map keys are compared with `==`, so their `equality` must be `shallow`"
+                    .to_string(),
+            );
+        }
+
+        return Ok(());
+    }
+
+    Err(format!(
+        "`{name}` has no conversion to a string. {SUPPORTED}"
+    ))
+}
+
+// === Invalid Records ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{reason}")]
+struct InvalidRecord {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    reason: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+/// Members of `Object`, which records already have
+pub(crate) const OBJECT_MEMBERS: &[&str] = &["hashCode", "runtimeType", "noSuchMethod", "toString"];
+
+fn invalid_records(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+    field: &Field,
+    ty: &Ty,
+) {
+    // fields with their own conversions don't use the record representation
+    let custom_json = field.to_json.is_some() && field.from_json.is_some();
+    let representation = context.library.record_representation_for(field);
+
+    let records = ty
+        .nested()
+        .into_iter()
+        .filter(|ty| matches!(ty.kind, TyKind::Record { .. }));
+
+    for record in records {
+        let TyKind::Record { named, .. } = &record.kind else {
+            unreachable!();
+        };
+
+        let mut invalid = |reason: String, help: Option<String>| {
+            let err = InvalidRecord {
+                src: source.clone(),
+                span: record.span.into(),
+                reason,
+                help,
+            };
+            errors.push(err.into());
+        };
+
+        let mut seen: Vec<&str> = vec![];
+        for (name, _) in named {
+            let is_positional_getter = name
+                .strip_prefix('$')
+                .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+
+            let reason = if seen.contains(&name.as_str()) {
+                Some(format!("Record field `{name}` is declared more than once"))
+            } else if name.starts_with('_') {
+                Some(format!("Record field `{name}` can't be private"))
+            } else if is_positional_getter {
+                Some(format!(
+                    "Record field `{name}` clashes with the getter of a positional field"
+                ))
+            } else if OBJECT_MEMBERS.contains(&name.as_str()) {
+                Some(format!(
+                    "Record field `{name}` clashes with a member of `Object`"
+                ))
+            } else if RESERVED_WORDS.contains(&name.as_str()) || !is_valid_identifier(name) {
+                Some(format!("`{name}` is not a valid record field name"))
+            } else {
+                None
+            };
+
+            if let Some(reason) = reason {
+                invalid(reason, None);
+            }
+            seen.push(name);
+        }
+
+        if !named.is_empty() && !custom_json && representation == RecordRepresentation::Array {
+            invalid(
+                format!("`{record}` has named fields, so it can't be written as a JSON array"),
+                Some(r#"Use `json-record-representation "object"` on the field"#.to_string()),
+            );
+        }
+    }
+}
+
+// === Unknown Types ===
+
+/// Types from `dart:core` which can be used without an import
+pub(crate) const DART_CORE_TYPES: &[&str] = &[
+    "BigInt",
+    "bool",
+    "Comparable",
+    "DateTime",
+    "double",
+    "Duration",
+    "dynamic",
+    "Enum",
+    "Error",
+    "Exception",
+    "Function",
+    "Future",
+    "int",
+    "Iterable",
+    "List",
+    "Map",
+    "Never",
+    "Null",
+    "num",
+    "Object",
+    "Pattern",
+    "Record",
+    "RegExp",
+    "Set",
+    "StackTrace",
+    "Stream",
+    "String",
+    "Symbol",
+    "Type",
+    "Uri",
+];
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Unknown type `{name}`")]
+struct UnknownType {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("not defined in this config or in `dart:core`")]
+    span: SourceSpan,
+
+    name: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Unknown type `{name}`, which may come from an import in the preamble")]
+#[diagnostic(severity(Warning))]
+struct PossiblyUnknownType {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("not defined in this config or in `dart:core`")]
+    span: SourceSpan,
+
+    name: String,
+
+    #[help]
+    help: String,
+}
+
+fn unknown_types(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+
+    let dart_snippets = || library.preamble.iter().chain(&library.postamble);
+
+    let mut local: Vec<&str> = library.type_names().map(|name| name.as_str()).collect();
+    local.extend(
+        library
+            .external_types
+            .iter()
+            .map(|external| external.name.as_str()),
+    );
+    local.extend(dart_snippets().flat_map(|dart| declared_type_names(dart)));
+
+    let mut known: Vec<&str> = DART_CORE_TYPES.to_vec();
+    known.extend(&local);
+    known.extend(
+        library
+            .defaults
+            .iter()
+            .flat_map(|d| &d.allowed_types)
+            .map(|name| name.as_str()),
+    );
+
+    let has_imports = dart_snippets()
+        .flat_map(|dart| dart.lines())
+        .any(|line| line.trim_start().starts_with("import "));
+    let prefixes: Vec<_> = dart_snippets()
+        .flat_map(|dart| import_prefixes(dart))
+        .collect();
+
+    for field in library.all_fields() {
+        let (Some(ty), _) = context.parse_ty(&field.ty) else {
+            continue;
+        };
+
+        let mut names = vec![];
+        simple_type_names(&ty, &mut names);
+
+        for (name, span) in names {
+            if known.contains(&name) {
+                continue;
+            }
+
+            // types from a prefixed import can't be checked, but the prefix can
+            if let Some((prefix, _)) = name.split_once('.') {
+                if prefixes.contains(&prefix) {
+                    continue;
+                }
+
+                let err = UnknownType {
+                    src: source.clone(),
+                    span,
+                    name: name.to_string(),
+                    help: Some(format!(
+                        "No import in the preamble or postamble uses the prefix `{prefix}`"
+                    )),
+                };
+                errors.push(err.into());
+                continue;
+            }
+
+            let suggestion = closest_name(name, &known);
+            let did_you_mean = suggestion.map(|suggestion| format!("did you mean `{suggestion}`?"));
+
+            // a near miss of a type from this config is a typo, not an import
+            let is_typo = suggestion.is_some_and(|suggestion| local.contains(&suggestion));
+
+            let err: miette::Report = if has_imports && !is_typo {
+                let allow = format!(
+                    "if `{name}` is imported, add `allowed-type \"{name}\"` to `defaults` to silence this warning"
+                );
+                PossiblyUnknownType {
+                    src: source.clone(),
+                    span,
+                    name: name.to_string(),
+                    help: match did_you_mean {
+                        Some(did_you_mean) => format!("{did_you_mean} Otherwise, {allow}"),
+                        None => allow,
+                    },
+                }
+                .into()
+            } else {
+                UnknownType {
+                    src: source.clone(),
+                    span,
+                    name: name.to_string(),
+                    help: did_you_mean,
                 }
+                .into()
+            };
+
+            errors.push(err);
+        }
+    }
+}
+
+/// All the names used as simple (non-collection) types anywhere in `ty`
+fn simple_type_names<'ty>(ty: &'ty Ty, out: &mut Vec<(&'ty str, SourceSpan)>) {
+    match &ty.kind {
+        TyKind::Simple(name) => out.push((name, ty.span.into())),
+        TyKind::Nullable(inner) | TyKind::List(inner) | TyKind::Set(inner) => {
+            simple_type_names(inner, out)
+        }
+        TyKind::Map { key, value } => {
+            simple_type_names(key, out);
+            simple_type_names(value, out);
+        }
+        TyKind::Record { positional, named } => {
+            for (_, ty) in record_fields(positional, named) {
+                simple_type_names(ty, out);
+            }
+        }
+        TyKind::Generic { name, args } => {
+            out.push((name, ty.span.into()));
+            for arg in args {
+                simple_type_names(arg, out);
+            }
+        }
+    }
+}
+
+/// The prefixes of imports in a snippet of Dart code, e.g. `x` in `import "x.dart" as x;`
+fn import_prefixes(dart: &str) -> Vec<&str> {
+    dart.lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("import "))
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" as ")?;
+            rest.split(|c: char| c.is_whitespace() || c == ';')
+                .next()
+                .filter(|prefix| !prefix.is_empty())
+        })
+        .collect()
+}
+
+/// A rough scan for the names of types declared in a snippet of Dart code, e.g. `class Foo`
+fn declared_type_names(dart: &str) -> Vec<&str> {
+    let words: Vec<_> = dart
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    words
+        .windows(2)
+        .filter(|pair| matches!(pair[0], "class" | "enum" | "mixin" | "typedef" | "type"))
+        .map(|pair| pair[1])
+        .collect()
+}
+
+/// The known name with the smallest edit distance to `name`, if any are close enough to be a
+/// plausible typo
+pub(crate) fn closest_name<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let threshold = (name.chars().count() / 3).max(1);
+
+    known
+        .iter()
+        .map(|candidate| (*candidate, edit_distance(name, candidate)))
+        .filter(|(_, distance)| *distance <= threshold)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(candidate, _)| candidate)
+}
+
+/// The Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            let insertion = current[j] + 1;
+            let deletion = previous[j + 1] + 1;
+
+            current.push(substitution.min(insertion).min(deletion));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// === One-way External Type Conversions ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("External type `{name}` defines `{defined}` but not `{missing}`")]
+#[diagnostic(help = "Either define both `to-json` and `from-json`, or neither")]
+struct OneWayExternalConversion {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("`{defined}` defined here")]
+    span: SourceSpan,
+
+    name: String,
+    defined: &'static str,
+    missing: &'static str,
+}
+
+fn one_way_external_conversion(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let errs = context
+        .library
+        .external_types
+        .iter()
+        .filter_map(|external| {
+            let (span, defined, missing) = match (&external.to_json, &external.from_json) {
+                (Some(to_json), None) => (to_json.span, "to-json", "from-json"),
+                (None, Some(from_json)) => (from_json.span, "from-json", "to-json"),
+                _ => return None,
+            };
+
+            Some(OneWayExternalConversion {
+                src: source.clone(),
+                span,
+                name: external.name.to_string(),
+                defined,
+                missing,
+            })
+        });
+
+    errors.extend(errs.map(Into::into));
+}
+
+// === Imports in Part Files ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("The generated file is a `part of` another library, so it can't import `{uri}`")]
+#[diagnostic(
+    severity(Warning),
+    help = "Make sure the library that the generated file is a part of imports it"
+)]
+struct ImportInPartFile {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("needed by this external type")]
+    span: SourceSpan,
+
+    uri: String,
+}
+
+fn imports_in_part_files(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+    if !library.is_part_file() {
+        return;
+    }
+
+    for uri in library.external_imports() {
+        // deep equality needs `package:collection`, rather than an import of the type itself
+        let external = library
+            .external_types
+            .iter()
+            .find(|external| external.imports.iter().any(|import| **import == uri))
+            .or_else(|| {
+                library
+                    .external_types
+                    .iter()
+                    .find(|external| library.equality_for(&external.name) == Equality::Deep)
+            })
+            .expect("every import comes from an external type");
+
+        let err = ImportInPartFile {
+            src: source.clone(),
+            span: external.name.span,
+            uri: uri.to_string(),
+        };
+        errors.push(err.into());
+    }
+}
+
+// === `when` Parameter Collisions ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "Union member `{name}` would use the parameter name `{param}` in `when`, which is {reason}"
+)]
+#[diagnostic(help = "Rename the member, or disable `generate-when` for this union")]
+struct WhenParameterCollision {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    name: String,
+    param: String,
+    reason: &'static str,
+}
+
+fn when_parameter_collision(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+
+    for union in library
+        .all_unions()
+        .filter(|union| library.generates_when(union))
+    {
+        let members = library.when_members(union);
+
+        for (index, (name, param)) in members.iter().enumerate() {
+            let taken = param == "orElse"
+                || members[..index]
+                    .iter()
+                    .any(|(_, previous)| previous == param);
+
+            // parameters follow the same rules as field names
+            let reason = if taken {
+                "already taken"
+            } else if RESERVED_WORDS.contains(&param.as_str()) {
+                "a reserved word"
+            } else {
+                continue;
+            };
+
+            let err = WhenParameterCollision {
+                src: source.clone(),
+                span: name.span,
+                name: name.to_string(),
+                param: param.clone(),
+                reason,
+            };
+            errors.push(err.into());
+        }
+    }
+}
+
+// === Invalid Class Hierarchies ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{reason}")]
+struct InvalidClassHierarchy {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    reason: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+fn invalid_class_hierarchy(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+    let mut push = |span: SourceSpan, reason: String, help: Option<&str>| {
+        let err = InvalidClassHierarchy {
+            src: source.clone(),
+            span,
+            reason,
+            help: help.map(str::to_string),
+        };
+        errors.push(err.into());
+    };
+
+    for union in library.all_unions() {
+        if library.is_sealed(union) {
+            for modifier in [&union.modifier, &union.builder_modifier]
+                .into_iter()
+                .flatten()
+            {
+                push(
+                    modifier.span,
+                    format!("Sealed union `{}` cannot have a class modifier", union.name),
+                    Some(
+                        "Sealed classes are implicitly abstract and cannot be combined with `final`, `base` or `interface`",
+                    ),
+                );
+            }
+
+            continue;
+        }
+
+        let union_modifier = library
+            .union_modifier(union)
+            .map_or(ClassModifier::Final, |modifier| modifier.value);
+        let union_builder_modifier = library
+            .union_builder_modifier(union)
+            .map_or(ClassModifier::Final, |modifier| modifier.value);
+
+        let class_modifiers = union.classes.iter().map(|class| {
+            let modifiers = [
+                library.class_modifier(class),
+                library.class_builder_modifier(class),
+            ];
+            (&class.name, modifiers)
+        });
+
+        // sealed nested unions are always allowed
+        let union_modifiers = union
+            .unions
+            .iter()
+            .filter(|nested| !library.is_sealed(nested))
+            .map(|nested| {
+                let modifiers = [
+                    library.union_modifier(nested),
+                    library.union_builder_modifier(nested),
+                ];
+                (&nested.name, modifiers)
+            });
+
+        for (name, [modifier, builder_modifier]) in class_modifiers.chain(union_modifiers) {
+            let pairs = [
+                (union_modifier, modifier),
+                (union_builder_modifier, builder_modifier),
+            ];
+
+            for (parent, child) in pairs {
+                let Some(child) = child else { continue };
+
+                if parent.restricts_subclasses() && !child.value.restricts_subclasses() {
+                    push(
+                        child.span,
+                        format!(
+                            "`{name}` extends a `{}` class, so it must be `final` or `base`",
+                            parent.prefix().trim(),
+                        ),
+                        Some(
+                            "Dart requires subclasses of `final` and `base` classes to be `base`, `final` or `sealed`",
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    for class in &library.classes {
+        let Some(extends) = &class.extends else {
+            continue;
+        };
+
+        if library
+            .type_names()
+            .any(|name| name.as_str() == extends.as_str())
+        {
+            push(
+                extends.span,
+                format!("`{extends}` is generated by `dart-typegen`, so it cannot be extended"),
+                Some("Consider using a `union` instead"),
+            );
+        }
+    }
+
+    for class in library.all_unions().flat_map(|union| &union.classes) {
+        let Some(extends) = &class.extends else {
+            continue;
+        };
+
+        push(
+            extends.span,
+            format!(
+                "`{}` is part of a union, so it cannot use `extends`",
+                class.name
+            ),
+            Some("Classes in a union always extend the union class"),
+        );
+    }
+
+    let class_mixins = library
+        .all_classes()
+        .flat_map(|class| class.with.iter().chain(&class.builder_with));
+    let union_mixins = library
+        .all_unions()
+        .flat_map(|union| union.with.iter().chain(&union.builder_with));
+
+    for mixin in class_mixins.chain(union_mixins) {
+        if library
+            .type_names()
+            .any(|name| name.as_str() == mixin.as_str())
+        {
+            push(
+                mixin.span,
+                format!(
+                    "`{mixin}` is generated by `dart-typegen`, so it cannot be used as a mixin"
+                ),
+                None,
+            );
+        }
+    }
+}
+
+// === Invalid JSON Representations ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{reason}")]
+struct InvalidJsonRepresentation {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    reason: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+fn invalid_json_representation(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+    let mut push = |span: SourceSpan, reason: String, help: Option<&str>| {
+        let err = InvalidJsonRepresentation {
+            src: source.clone(),
+            span,
+            reason,
+            help: help.map(str::to_string),
+        };
+        errors.push(err.into());
+    };
+
+    for union in library.all_unions() {
+        let representation = library.json_representation_for(union);
+        let span = union
+            .json_representation
+            .as_ref()
+            .map_or(union.name.span, |representation| representation.span);
+
+        // nested unions without their own setting inherit it, so the parent reports the error
+        let parent = library.parent_union(union);
+        let inherited = parent.is_some() && union.json_representation.is_none();
+        let nested = parent.is_some() || !union.unions.is_empty();
+
+        if nested && !inherited && representation != JsonRepresentation::Internal {
+            push(
+                span,
+                format!(
+                    "Union `{}` is part of a nested union, so it must use the `internal` JSON representation",
+                    union.name
+                ),
+                Some("Nested unions are identified by discriminant keys inside the JSON object"),
+            );
+        }
+
+        if let Some(content_key) = &union.json_content_key {
+            if representation != JsonRepresentation::Adjacent {
+                push(
+                    content_key.span,
+                    format!(
+                        "Union `{}` has a `json-content-key`, but is not adjacently tagged",
+                        union.name
+                    ),
+                    Some("`json-content-key` is only used with `json-representation \"adjacent\"`"),
+                );
+            } else if content_key.as_str() == library.discriminant_key_for(union) {
+                push(
+                    content_key.span,
+                    format!(
+                        "Union `{}` uses `{content_key}` as both its discriminant key and its content key",
+                        union.name
+                    ),
+                    None,
+                );
+            }
+        } else if representation == JsonRepresentation::Adjacent
+            && library.json_content_key_for(union) == library.discriminant_key_for(union)
+        {
+            push(
+                span,
+                format!(
+                    "Union `{}` uses `{}` as both its discriminant key and its content key",
+                    union.name,
+                    library.discriminant_key_for(union),
+                ),
+                Some("Set a different `json-content-key` or `json-discriminant`"),
+            );
+        }
+
+        match representation {
+            JsonRepresentation::Internal | JsonRepresentation::Adjacent => {}
+            JsonRepresentation::External => {
+                for class in &union.classes {
+                    let Some(value) = &class.json_discriminant_value else {
+                        continue;
+                    };
+
+                    if !matches!(*value.literal, Literal::String(_)) {
+                        push(
+                            (*value.literal.span()).into(),
+                            format!(
+                                "`{}` is part of an externally tagged union, so its discriminant value must be a string",
+                                class.name
+                            ),
+                            Some(
+                                "Externally tagged unions use the discriminant value as a JSON key",
+                            ),
+                        );
+                    }
+                }
+            }
+            JsonRepresentation::Untagged => {
+                let discriminant_spans = union
+                    .json_discriminant
+                    .iter()
+                    .map(|key| key.span)
+                    .chain(
+                        union
+                            .json_discriminant_value_case
+                            .iter()
+                            .map(|case| case.span),
+                    )
+                    .chain(
+                        union
+                            .classes
+                            .iter()
+                            .flat_map(|class| &class.json_discriminant_value)
+                            .map(|value| (*value.literal.span()).into()),
+                    );
+
+                for span in discriminant_spans {
+                    push(
+                        span,
+                        format!(
+                            "Union `{}` is untagged, so it has no discriminant",
+                            union.name
+                        ),
+                        Some("Remove this, or use a different `json-representation`"),
+                    );
+                }
+
+                let members = context.untagged_members(union);
+                for (index, (class, keys)) in members.iter().enumerate() {
+                    let mut keys = keys.clone();
+                    keys.sort();
+
+                    let clash = members[..index].iter().find(|(_, other)| {
+                        let mut other = other.clone();
+                        other.sort();
+                        other == keys
+                    });
+
+                    if let Some((other, _)) = clash {
+                        push(
+                            class.name.span,
+                            format!(
+                                "`{}` and `{}` require the same JSON keys, so they cannot be told apart in an untagged union",
+                                other.name, class.name
+                            ),
+                            Some(
+                                "Make a field required in one member but not the other, or use a tagged `json-representation`",
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+// === Invalid Orderings ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{reason}")]
+struct InvalidOrdering {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    reason: String,
+
+    #[help]
+    help: Option<String>,
+}
+
+/// Dart core types that don't implement `Comparable`
+const UNORDERED_TYPES: &[&str] = &[
+    "Object", "dynamic", "Null", "Function", "Type", "Symbol", "Uri", "Record", "Iterable",
+];
+
+fn invalid_orderings(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let mut push = |span: SourceSpan, reason: String, help: Option<String>| {
+        let err = InvalidOrdering {
+            src: source.clone(),
+            span,
+            reason,
+            help,
+        };
+        errors.push(err.into());
+    };
+
+    for class in context.library.all_classes() {
+        let Some(ordering) = &class.ordering else {
+            continue;
+        };
+
+        if ordering.fields.is_empty() {
+            push(
+                ordering.span.into(),
+                "`ordering` has no fields".to_string(),
+                Some("add a `field` child for each field to sort by".to_string()),
+            );
+        }
+
+        let names: Vec<_> = class.fields.iter().map(|f| f.name.as_str()).collect();
+        for (index, key) in ordering.fields.iter().enumerate() {
+            if ordering.fields[..index]
+                .iter()
+                .any(|k| k.name.value == key.name.value)
+            {
+                push(
+                    key.name.span,
+                    format!("`{}` is already part of the ordering", key.name),
+                    None,
+                );
+                continue;
+            }
+
+            let Some(field) = class.fields.iter().find(|f| f.name.value == key.name.value) else {
+                let help =
+                    closest_name(&key.name, &names).map(|name| format!("did you mean `{name}`?"));
+                push(
+                    key.name.span,
+                    format!("`{}` is not a field of `{}`", key.name, class.name),
+                    help,
+                );
+                continue;
+            };
+
+            let Some(ty) = context.parse_ty(&field.ty).0 else {
+                continue;
+            };
+
+            if let Some(nulls) = &key.order_nulls
+                && !matches!(ty.kind, TyKind::Nullable(_))
+            {
+                push(
+                    nulls.span,
+                    format!(
+                        "`order-nulls` only applies to nullable fields, but `{}` is `{ty}`",
+                        key.name
+                    ),
+                    None,
+                );
+            }
+
+            if let Err(help) = orderable(context, &ty) {
+                push(
+                    field.ty.span,
+                    format!(
+                        "`{}` can't be ordered by, since `{ty}` isn't comparable",
+                        key.name
+                    ),
+                    Some(help),
+                );
+            }
+        }
+    }
+}
+
+/// Whether `compareTo` can compare values of `ty`, or why not
+fn orderable(context: &Context, ty: &Ty) -> std::result::Result<(), String> {
+    let ty = match &ty.kind {
+        TyKind::Nullable(inner) => inner,
+        _ => ty,
+    };
+
+    let ident = match &ty.kind {
+        TyKind::Simple(ident) | TyKind::Generic { name: ident, .. } => ident.as_str(),
+        _ => return Err("only single values can be ordered, not collections or records".into()),
+    };
+
+    let library = &context.library;
+    if let Some(class) = library
+        .all_classes()
+        .find(|class| class.name.as_str() == ident)
+    {
+        return match class.ordering {
+            Some(_) => Ok(()),
+            None => Err(format!("give `{ident}` an `ordering` of its own")),
+        };
+    }
+    if library
+        .all_unions()
+        .any(|union| union.name.as_str() == ident)
+    {
+        return Err("unions can't be ordered".into());
+    }
+    if UNORDERED_TYPES.contains(&ident) {
+        return Err(format!("`{ident}` doesn't implement `Comparable`"));
+    }
+
+    Ok(())
+}
+
+// === Generated Member Collisions ===
+
+/// Members of every generated class or builder, which fields can't share a name with
+pub(crate) const GENERATED_MEMBERS: &[&str] = &[
+    "build",
+    "fromJson",
+    "rebuild",
+    "toBuilder",
+    "toJson",
+    "update",
+];
+
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "`{name}` cannot be used as the name of a {kind}, since it clashes with the generated `{name}`"
+)]
+struct GeneratedMemberCollision {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label]
+    span: SourceSpan,
+
+    name: String,
+    kind: &'static str,
+
+    #[help]
+    help: String,
+}
+
+fn generated_member_collisions(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+
+    let mut push = |name: &SpannedScalar<String>, kind: &'static str, help: &str| {
+        let err = GeneratedMemberCollision {
+            src: source.clone(),
+            span: name.span,
+            name: name.to_string(),
+            kind,
+            help: help.replace("{name}", name),
+        };
+        errors.push(err.into());
+    };
+
+    for class in library.all_classes() {
+        let mut members = GENERATED_MEMBERS.to_vec();
+        if class.ordering.is_some() {
+            members.push("compareTo");
+        }
+        if library.builder_mode_for(class) == BuilderMode::Partial {
+            members.extend(["isComplete", "missingFields"]);
+        }
+
+        for field in &class.fields {
+            if members.contains(&field.name.as_str()) {
+                push(
+                    &field.name,
+                    "field",
+                    r#"Try renaming the field and adding `json-key "{name}"` to keep the same JSON key"#,
+                );
+            }
+        }
+    }
+
+    for enumeration in &library.enums {
+        if !enumeration.comparable.as_ref().is_some_and(|c| c.value) {
+            continue;
+        }
+
+        for variant in &enumeration.variants {
+            if variant.name.as_str() == "compareTo" {
+                push(
+                    &variant.name,
+                    "variant",
+                    r#"Try renaming the variant and adding `json-value "{name}"` to keep the same JSON value"#,
+                );
+            }
+        }
+    }
+}
+
+// === Invalid Examples ===
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("Example can't be decoded as `{ty}`")]
+struct InvalidExample {
+    #[source_code]
+    src: NamedSource<String>,
+
+    #[label("example defined here")]
+    span: SourceSpan,
+
+    ty: String,
+
+    #[related]
+    errors: Vec<miette::Report>,
+}
+
+fn invalid_examples(
+    context: &Context,
+    errors: &mut Vec<miette::Report>,
+    source: &NamedSource<String>,
+) {
+    let library = &context.library;
+    let classes = library
+        .all_classes()
+        .map(|class| (&class.name, &class.examples));
+    let unions = library
+        .all_unions()
+        .map(|union| (&union.name, &union.examples));
+    let enums = library
+        .enums
+        .iter()
+        .map(|enumeration| (&enumeration.name, &enumeration.examples));
+
+    for (name, examples) in classes.chain(unions).chain(enums) {
+        for example in examples {
+            let mismatches = match parse_embedded(source, example) {
+                Ok(json) => context.json_mismatches(source, &json, name),
+                Err(error) => vec![error],
+            };
+
+            if !mismatches.is_empty() {
+                let error = InvalidExample {
+                    src: source.clone(),
+                    span: example.span,
+                    ty: name.to_string(),
+                    errors: mismatches,
+                };
+                errors.push(error.into());
             }
         }
     }
@@ -32,4 +32,851 @@ fn validate_tests() {
             }
         "#,
     );
+
+    // external type with a conversion in only one direction
+    assert_error_count(
+        1,
+        /* kdl */
+        r#"
+            external-type "Money" {
+                to-json "(money) => money.toJson()"
+            }
+        "#,
+    );
+
+    // external type with the same name as a generated class
+    assert_error_count(
+        2,
+        /* kdl */ r#"
+            external-type "Money"
+            class "Money"
+        "#,
+    );
+
+    // modifiers on a sealed union
+    assert_error_count(
+        2,
+        /* kdl */ r#"
+            union "Foo" sealed=true {
+                modifier "base"
+                builder-modifier "final"
+                class "X"
+            }
+        "#,
+    );
+
+    // union members must be `final` or `base` if the union is `final` or `base`
+    assert_error_count(
+        2,
+        /* kdl */ r#"
+            union "Foo" {
+                class "X" {
+                    modifier "none"
+                    builder-modifier "interface"
+                }
+                class "Y" {
+                    modifier "base"
+                }
+            }
+        "#,
+    );
+
+    // union members can have any modifier if the union allows it
+    assert_error_count(
+        0,
+        /* kdl */ r#"
+            union "Foo" {
+                modifier "interface"
+                builder-modifier "none"
+                class "X" {
+                    modifier "none"
+                    builder-modifier "interface"
+                }
+            }
+        "#,
+    );
+
+    // union members already extend the union
+    assert_error_count(
+        1,
+        /* kdl */ r#"
+            union "Foo" {
+                class "X" {
+                    extends "Object"
+                }
+            }
+        "#,
+    );
+
+    // generated types can't be extended or mixed in
+    assert_error_count(
+        2,
+        /* kdl */ r#"
+            class "Foo"
+            class "Bar" {
+                extends "Foo"
+                with "Foo"
+            }
+        "#,
+    );
+
+    // empty nested union
+    assert_error_count(
+        1,
+        /* kdl */ r#"
+            union "Foo" {
+                union "Bar" {}
+            }
+        "#,
+    );
+
+    // duplicate names across nesting levels
+    assert_error_count(
+        2,
+        /* kdl */ r#"
+            union "Foo" {
+                union "Bar" {
+                    class "X"
+                }
+                class "X" {
+                    json-discriminant-value "other"
+                }
+            }
+        "#,
+    );
+
+    // discriminant values on unions that aren't dispatched by value
+    assert_error_count(
+        2,
+        /* kdl */ r#"
+            union "Foo" {
+                json-discriminant-value "foo"
+                union "Bar" {
+                    json-discriminant-value "bar"
+                    class "X"
+                }
+            }
+        "#,
+    );
+
+    // a nested union can't reuse the key of a union further out
+    assert_error_count(
+        1,
+        /* kdl */ r#"
+            union "Foo" {
+                union "Bar" {
+                    json-discriminant "kind"
+                    union "Baz" {
+                        json-discriminant "type"
+                        class "X"
+                    }
+                }
+            }
+        "#,
+    );
+
+    // `when` parameters are derived from member names, so they must be unique and can't be
+    // reserved words
+    assert_error_count(
+        4,
+        /* kdl */
+        r#"
+            union "Foo" {
+                generate-when true
+                class "OrElse"
+                class "FooBar"
+                class "fooBar" allow-non-pascal-case=true
+                class "Default"
+                class "Switch"
+            }
+        "#,
+    );
+
+    // nested unions are always internally tagged
+    assert_error_count(
+        1,
+        /* kdl */
+        r#"
+            union "Foo" {
+                json-representation "external"
+                union "Bar" {
+                    class "X"
+                }
+            }
+        "#,
+    );
+
+    // untagged unions have no discriminant
+    assert_error_count(
+        2,
+        /* kdl */
+        r#"
+            union "Foo" {
+                json-representation "untagged"
+                json-discriminant "kind"
+                class "X" {
+                    json-discriminant-value "x"
+                    field "x" type="int"
+                }
+            }
+        "#,
+    );
+
+    // untagged members must be distinguishable by their required keys
+    assert_error_count(
+        1,
+        /* kdl */
+        r#"
+            union "Foo" {
+                json-representation "untagged"
+                class "X" {
+                    field "a" type="int"
+                    field "b" type="int?"
+                }
+                class "Y" {
+                    field "a" type="String"
+                    field "c" type="int" {
+                        defaults-to 1
+                    }
+                }
+                class "Z" {
+                    field "a" type="int"
+                    field "d" type="int"
+                }
+            }
+        "#,
+    );
+
+    // content keys only make sense for adjacently tagged unions, and must differ from the
+    // discriminant key
+    assert_error_count(
+        2,
+        /* kdl */
+        r#"
+            union "Foo" {
+                json-content-key "data"
+                class "X"
+            }
+            union "Bar" {
+                json-representation "adjacent"
+                json-discriminant "data"
+                class "X2"
+            }
+        "#,
+    );
+
+    // externally tagged unions use discriminant values as keys
+    assert_error_count(
+        1,
+        /* kdl */
+        r#"
+            union "Foo" {
+                json-representation "external"
+                class "X" {
+                    json-discriminant-value 1
+                }
+                class "Y" {
+                    json-discriminant-value "y"
+                }
+            }
+        "#,
+    );
+
+    // discriminant values must be unique, including values produced by renaming
+    assert_error_count(
+        2,
+        /* kdl */
+        r#"
+            union "Foo" {
+                json-discriminant-value-case "snake"
+                class "FooBar"
+                class "Foo_Bar" allow-non-pascal-case=true
+                class "Baz" {
+                    json-discriminant-value "qux"
+                }
+                class "Qux" {
+                    json-discriminant-value "qux"
+                }
+            }
+        "#,
+    );
+
+    // flattened nested unions share their parent's discriminant values
+    assert_error_count(
+        1,
+        /* kdl */
+        r#"
+            union "Foo" {
+                class "X"
+                union "Bar" {
+                    class "Y" {
+                        json-discriminant-value "X"
+                    }
+                }
+            }
+        "#,
+    );
+
+    // fields can't use the discriminant key of an internally tagged union
+    assert_error_count(
+        2,
+        /* kdl */
+        r#"
+            union "Foo" {
+                json-discriminant "kind"
+                class "X" {
+                    field "kind" type="String"
+                }
+                union "Bar" {
+                    class "Y" {
+                        field "other" type="String" {
+                            json-key "kind"
+                        }
+                    }
+                }
+            }
+            union "Baz" {
+                json-representation "adjacent"
+                class "Z" {
+                    field "type" type="String"
+                }
+            }
+        "#,
+    );
+
+    // unknown types, including types nested in collections
+    assert_error_count(
+        3,
+        /* kdl */
+        r#"
+            class "Foo" {
+                field "a" type="Colour"
+                field "b" type="Map<String, List<Fooo>>"
+                field "c" type="Set<Whatever?>"
+                field "d" type="Color?"
+                field "e" type="DateTime"
+            }
+            enum "Color" {
+                variant "red"
+            }
+        "#,
+    );
+
+    // types declared in the preamble/postamble, external types and allowed types are known
+    assert_error_count(
+        0,
+        /* kdl */
+        r##"
+            defaults {
+                allowed-type "Decimal"
+            }
+            preamble r#"
+                typedef Id = String;
+            "#
+            postamble r#"
+                class Point {}
+                extension type Meters(double value) {}
+            "#
+            external-type "Money" {
+                to-json "(m) => m.cents"
+                from-json "(j) => Money(j as int)"
+            }
+            class "Foo" {
+                field "a" type="Id"
+                field "b" type="Point"
+                field "c" type="Meters"
+                field "d" type="Money"
+                field "e" type="Decimal"
+            }
+        "##,
+    );
+
+    // unknown types are only warnings if they might come from an import
+    assert_error_count(
+        1,
+        /* kdl */
+        r##"
+            preamble r#"
+                import "package:decimal/decimal.dart";
+            "#
+            class "Foo" {
+                field "a" type="Decimal"
+            }
+        "##,
+    );
+
+    // reserved words and invalid identifiers
+    assert_error_count(
+        5,
+        /* kdl */
+        r#"
+            class "Function" {
+                field "class" type="String"
+                field "my field" type="String"
+                field "in-stock" type="bool"
+                field "required" type="bool"
+                field "$value" type="int"
+            }
+            enum "Color" {
+                variant "null"
+                variant "set"
+            }
+        "#,
+    );
+
+    // defaults must match the field type
+    assert_error_count(
+        7,
+        /* kdl */
+        r#"
+            class "Foo" {
+                field "a" type="int" {
+                    defaults-to "abc"
+                }
+                field "b" type="String" {
+                    defaults-to null
+                }
+                field "c" type="double" {
+                    defaults-to 1
+                }
+                field "d" type="num?" {
+                    defaults-to 1.5
+                }
+                field "e" type="bool" {
+                    defaults-to 1
+                }
+                field "f" type="List<int>" {
+                    defaults-to 1
+                }
+                field "g" type="Color" {
+                    defaults-to "red"
+                }
+                field "h" type="Color" {
+                    defaults-to "gren"
+                }
+                field "i" type="Color?" {
+                    defaults-to 1
+                }
+                field "j" type="Bar" {
+                    defaults-to "bar"
+                }
+            }
+            class "Bar"
+            enum "Color" {
+                variant "red"
+                variant "green"
+            }
+        "#,
+    );
+
+    // constraints must suit the field type, and have a non-empty range
+    assert_error_count(
+        6,
+        /* kdl */
+        r#"
+            class "Foo" {
+                field "a" type="String" {
+                    min 1
+                    pattern "[a-z]+"
+                    min-length 2
+                    max-length 1
+                }
+                field "b" type="int?" {
+                    min 10
+                    max 1
+                    max-items 3
+                }
+                field "c" type="int" {
+                    min 0.5
+                }
+                field "d" type="Set<int>" {
+                    min-items 1
+                    max-items 2
+                    min-length 1
+                }
+                field "e" type="double" {
+                    min 1
+                    max 2.5
+                }
+            }
+        "#,
+    );
+
+    // structured defaults must match the field type
+    assert_error_count(
+        10,
+        /* kdl */
+        r#"
+            class "Foo" {
+                field "a" type="List<int>" {
+                    defaults-to {
+                        item 1
+                        item "two"
+                        entry "three" 3
+                    }
+                }
+                field "b" type="Map<String, int>" {
+                    defaults-to {
+                        entry 1 2
+                    }
+                }
+                field "c" type="Bar" {
+                    defaults-to {
+                        field "y" 1
+                        field "y" 2
+                        field "z" 3
+                    }
+                }
+                field "d" type="Baz" {
+                    defaults-to {
+                        field "x" 1
+                    }
+                }
+                field "e" type="int" {
+                    defaults-to 1 2
+                }
+                field "f" type="List<int>" {
+                    defaults-to 1 {
+                        item 1
+                    }
+                }
+                field "g" type="Bar?" {
+                    defaults-to {
+                        field "x" 1
+                        field "y" 2
+                    }
+                }
+            }
+            class "Bar" {
+                field "x" type="int"
+                field "y" type="int"
+            }
+            union "Baz" {
+                class "Qux"
+            }
+        "#,
+    );
+
+    // map keys need a conversion to and from a string
+    assert_error_count(
+        6,
+        /* kdl */
+        r#"
+            external-type "Id" {
+                to-json "(id) => id.value"
+                from-json "(json) => Id(json as String)"
+            }
+            external-type "Tags" {
+                to-json "(tags) => tags.join()"
+                from-json "(json) => Tags(json as String)"
+                equality "deep"
+            }
+            class "Foo" {
+                field "a" type="Map<int, String>"
+                field "b" type="Map<Color, double>"
+                field "c" type="List<Map<Id, Map<double, int>>>"
+                field "d" type="Map<bool, int>"
+                field "e" type="Map<Bar, int>"
+                field "f" type="Map<String?, int>"
+                field "g" type="Map<String, Map<Code, int>>"
+                field "h" type="Map<Tags, int>"
+                field "i" type="Map<List<int>, int>"
+            }
+            class "Bar"
+            enum "Color" {
+                variant "red"
+            }
+            enum "Code" {
+                variant "ok" {
+                    json-value 200
+                }
+            }
+        "#,
+    );
+
+    // records need valid named fields, and named fields can only be written as JSON objects
+    assert_error_count(
+        7,
+        /* kdl */
+        r#"
+            class "Foo" {
+                field "a" type="({int x, int x})" {
+                    json-record-representation "object"
+                }
+                field "b" type="({int _x, int $1, String hashCode})" {
+                    json-record-representation "object"
+                }
+                field "c" type="List<(int, {int y})>"
+                field "d" type="(int, {int y})" {
+                    to-json "(d) => [d.$1, d.y]"
+                    from-json "(json) => (json[0] as int, y: json[1] as int)"
+                }
+                field "e" type="Map<(int, int), String>"
+                field "f" type="(int, int)" {
+                    defaults-to 1
+                }
+            }
+        "#,
+    );
+
+    // collections need the right number of type arguments, and other generic types need a JSON
+    // conversion
+    assert_error_count(
+        6,
+        /* kdl */
+        r##"
+            preamble r#"
+                import "package:x/x.dart" as x;
+            "#
+            external-type "Optional" {
+                to-json "(o) => o.toJson()"
+                from-json "(json) => Optional.fromJson(json)"
+            }
+            class "Foo" {
+                field "a" type="List"
+                field "b" type="Map<String>"
+                field "c" type="Future<int>"
+                field "d" type="Optional<List<int>>"
+                field "e" type="Iterable<int>" {
+                    to-json "(e) => e.toList()"
+                    from-json "(json) => (json as List).cast<int>()"
+                }
+                field "f" type="x.Thing"
+                field "g" type="y.Thing"
+                field "h" type="List<x.Box<int>>"
+                field "i" type="Set<int, int>"
+            }
+        "##,
+    );
+
+    // orderings have to name comparable fields of the class, once each
+    assert_error_count(
+        0,
+        /* kdl */
+        r#"
+            enum "Level" {
+                variant "low"
+            }
+            class "Inner" {
+                ordering {
+                    field "a"
+                }
+                field "a" type="String"
+            }
+            class "Foo" {
+                ordering {
+                    field "a" order="desc"
+                    field "b" order-nulls="last"
+                    field "c"
+                    field "d"
+                }
+                field "a" type="bool"
+                field "b" type="DateTime?"
+                field "c" type="Level"
+                field "d" type="Inner"
+            }
+        "#,
+    );
+    assert_error_count(
+        8,
+        /* kdl */
+        r#"
+            class "Inner" {
+                field "a" type="String"
+            }
+            union "Shape" {
+                class "Circle"
+            }
+            class "Foo" {
+                ordering {
+                    field "a" order-nulls="first"
+                    field "a"
+                    field "z"
+                    field "b"
+                    field "c"
+                    field "d"
+                    field "e"
+                }
+                field "a" type="int"
+                field "b" type="List<int>"
+                field "c" type="Inner"
+                field "d" type="Shape?"
+                field "e" type="Object"
+            }
+            class "Bar" {
+                ordering
+            }
+        "#,
+    );
+
+    // part files can't import anything, so each needed import is a warning
+    assert_error_count(
+        2,
+        /* kdl */
+        r##"
+            preamble r#"
+                part of "models.dart";
+            "#
+            external-type "Money" {
+                import "package:money/money.dart"
+                equality "deep"
+            }
+            class "Foo" {
+                field "a" type="Money"
+            }
+        "##,
+    );
+
+    // fields can't share a name with generated members
+    assert_error_count(
+        0,
+        /* kdl */
+        r#"
+            enum "Method" {
+                variant "compareTo"
+            }
+            class "Foo" {
+                field "compareTo" type="int"
+                field "isComplete" type="bool"
+            }
+        "#,
+    );
+    assert_error_count(
+        10,
+        /* kdl */
+        r#"
+            enum "Method" {
+                comparable true
+                variant "compareTo"
+            }
+            class "Foo" {
+                ordering {
+                    field "toJson"
+                }
+                field "build" type="int"
+                field "fromJson" type="int"
+                field "toBuilder" type="int"
+                field "toJson" type="int"
+                field "compareTo" type="int"
+                field "rebuild" type="int"
+                field "update" type="int"
+            }
+            class "Form" {
+                builder-mode "partial"
+                field "isComplete" type="bool"
+                field "missingFields" type="List<String>"
+            }
+        "#,
+    );
+
+    // examples are decoded like the generated `fromJson`, using json keys, enum values and
+    // discriminants
+    assert_error_count(
+        0,
+        /* kdl */
+        r##"
+            enum "Color" {
+                example r#""two""#
+                variant "one"
+                variant "two"
+            }
+            class "Pet" {
+                example r#"{"pet_name": "Rex", "color": "one"}"#
+                field "name" type="String" {
+                    json-key "pet_name"
+                }
+                field "color" type="Color?"
+            }
+            union "Shape" {
+                json-discriminant "kind"
+                example r#"{"kind": "Circle", "radius": 1.0}"#
+                class "Circle" {
+                    field "radius" type="double"
+                }
+            }
+        "##,
+    );
+
+    // warnings don't stop examples from being checked
+    assert_error_count(
+        2,
+        /* kdl */
+        r##"
+            preamble r#"
+                import "package:decimal/decimal.dart";
+            "#
+            class "Pet" {
+                example r#"{"name": 1}"#
+                field "name" type="String"
+                field "price" type="Decimal?"
+            }
+        "##,
+    );
+
+    // every example that can't be decoded is reported once
+    assert_error_count(
+        5,
+        /* kdl */
+        r##"
+            enum "Color" {
+                example r#""three""#
+                variant "one"
+            }
+            class "Pet" {
+                example r#"{"name": "Rex", "color": null}"#
+                example r#"{"pet_name": "Rex", "color": "two"}"#
+                example r#"{"pet_name": "Rex",}"#
+                field "name" type="String" {
+                    json-key "pet_name"
+                }
+                field "color" type="Color"
+            }
+            union "Shape" {
+                example r#"{"type": "Square"}"#
+                class "Circle"
+            }
+        "##,
+    );
+}
+
+#[test]
+fn example_errors_point_into_the_example() {
+    let source = /* kdl */ r##"
+        class "Pet" {
+            example r#"{"name": 1}"#
+            example "{\"name\": 1}"
+            field "name" type="String"
+        }
+    "##;
+    let ctx = Context::from_str(source).unwrap();
+    let named_source = ctx.named_source();
+    let class = &ctx.library.classes[0];
+
+    let raw = parse_embedded(&named_source, &class.examples[0]).unwrap();
+    let value = raw.get("name").unwrap();
+    assert_eq!(&source[value.span.offset()..][..value.span.len()], "1");
+
+    // Escapes make the offsets unreliable, so the whole string is used instead
+    let escaped = parse_embedded(&named_source, &class.examples[1]).unwrap();
+    assert_eq!(escaped.get("name").unwrap().span, class.examples[1].span);
+}
+
+#[test]
+fn imports_dont_hide_typos_of_local_types() {
+    let source = /* kdl */ r##"
+        preamble r#"
+            import "package:decimal/decimal.dart";
+        "#
+        enum "Color" {
+            variant "red"
+        }
+        class "Foo" {
+            field "a" type="Colour"
+            field "b" type="Decimal"
+        }
+    "##;
+    let ctx = Context::from_str(source).unwrap();
+    let severities: Vec<_> = ctx.collect_errors().iter().map(|e| e.severity()).collect();
+
+    assert_eq!(severities, [None, Some(Severity::Warning)]);
 }